@@ -1,5 +1,146 @@
-use libcosyc_compiler as compiler;
+use libcosyc_compiler::{ self as compiler, Backend, Emit, Options };
+use libcosyc_diagnostic::error::WarningMode;
+use std::{ env, process };
+
+const USAGE : &str = "\
+usage: cosyc [build | run | eval] <file> [options]
+
+commands:
+  build                   compile <file> into a native executable
+  run                     compile <file> using the LLVM JIT and print its value
+  eval                    evaluate <file> using the interpreter and print its value
+
+options:
+  -o, --output <path>     write the output to <path>
+  -b, --backend <name>    generate code using `c` or `llvm` (default `c`)
+  -e, --emit <stage>      stop after <stage> and emit its output, where <stage> is
+                          `tokens`, `ast`, `ir`, `c`, `llvm-ir`, `llvm-bc`, `asm`,
+                          `obj` or `exe`
+  -O, --opt-level <n>     set the optimisation level from 0 to 3 (default 0)
+  -w, --no-warnings       suppress all warnings
+  -W, --deny-warnings     treat warnings as errors
+  -h, --help              display this message and exit
+  -v, --version           display the compiler version and exit";
+
+/// Represents the action requested by the command-line arguments.
+enum Command {
+    Help,
+    Version,
+    Compile {
+        path : String,
+        opts : Options,
+        warnings : WarningMode,
+        mode : Mode
+    }
+}
+
+/// Represents the different ways a program can be handled.
+enum Mode {
+    Compile,
+    Run,
+    Eval
+}
+
+/// Splits arguments of the form `--name=value` into their name and value.
+fn split_flag(arg : &str) -> (&str, Option<&str>) {
+    if arg.starts_with("--") {
+        if let Some(i) = arg.find('=') {
+            return (&arg[..i], Some(&arg[i + 1..]));
+        }
+    }
+    (arg, None)
+}
+
+/// Parses the command-line arguments into a command.
+fn parse_args(args : impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter().peekable();
+    let (build, mode) = match args.peek().map(String::as_str) {
+        Some("build") => (true, Mode::Compile),
+        Some("run") => (false, Mode::Run),
+        Some("eval") => (false, Mode::Eval),
+        _ => (false, Mode::Compile)
+    };
+    if build || !matches!(mode, Mode::Compile) {
+        args.next();
+    }
+    let mut path = None;
+    let mut opts = Options::default();
+    let mut warnings = WarningMode::default();
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = split_flag(&arg);
+        let mut value = || inline_value
+                .map(str::to_string)
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for option `{}`", flag));
+        match flag {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-v" | "--version" => return Ok(Command::Version),
+            "-o" | "--output" => opts.output = Some(value()?),
+            "-b" | "--backend" => {
+                let name = value()?;
+                opts.backend = Backend::from_name(&name)
+                        .ok_or_else(|| format!("unknown backend `{}`", name))?;
+            },
+            "-e" | "--emit" => {
+                let name = value()?;
+                opts.emit = Some(Emit::from_name(&name)
+                        .ok_or_else(|| format!("unknown emit stage `{}`", name))?);
+            },
+            "-O0" | "-O1" | "-O2" | "-O3" => opts.opt_level = flag[2..].parse().unwrap(),
+            "-O" | "--opt-level" => {
+                let level = value()?;
+                opts.opt_level = match level.parse() {
+                    Ok(n) if n <= 3 => n,
+                    _ => return Err(format!("invalid optimisation level `{}`", level))
+                };
+            },
+            "-w" | "--no-warnings" => warnings = WarningMode::Ignore,
+            "-W" | "--deny-warnings" => warnings = WarningMode::Deny,
+            x if x.starts_with('-') && x.len() > 1 => {
+                return Err(format!("unknown option `{}`", x));
+            },
+            _ => {
+                if path.is_some() {
+                    return Err(format!("unexpected argument `{}`", arg));
+                }
+                path = Some(arg);
+            }
+        }
+    }
+    let path = path.ok_or("no input file was supplied")?;
+    if build && opts.emit.is_none() {
+        opts.emit = Some(Emit::Exe);
+    }
+    Ok(Command::Compile { path, opts, warnings, mode })
+}
 
 fn main() {
-    compiler::test();
+    let command = match parse_args(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(msg) => {
+            eprintln!("error: {}\n\n{}", msg, USAGE);
+            process::exit(2);
+        }
+    };
+    match command {
+        Command::Help => println!("{}", USAGE),
+        Command::Version => println!("cosyc {}", env!("CARGO_PKG_VERSION")),
+        Command::Compile { path, opts, warnings, mode } => {
+            let mut sess = compiler::open(&path);
+            sess.issues.set_warning_mode(warnings);
+            if !sess.errors_occurred() {
+                match mode {
+                    Mode::Compile => compiler::compile(&mut sess, &opts),
+                    Mode::Run => compiler::run(&mut sess, &opts),
+                    Mode::Eval => compiler::eval(&mut sess)
+                };
+            }
+            if sess.issues_occurred() {
+                eprintln!("{}", sess);
+            }
+            if sess.errors_occurred() {
+                process::exit(1);
+            }
+        }
+    }
 }
@@ -1,15 +1,99 @@
 use libcosyc_diagnostic::{
     error::{ CompilerError, IssueTracker, Failable },
-    source::Renderable
+    source::{ Span, Renderable }
 };
 use libcosyc_ir::ir;
-use std::fmt::Write;
+use std::{ fmt::Write, collections::{ HashMap, HashSet } };
 
 /// Returns the c representation of a local variable with this index.
 fn c_local(local : usize) -> String {
     format!("t{}", local)
 }
 
+/// Returns a C identifier made from this prefix and name. Characters which cannot
+/// appear in C identifiers are replaced by their code point.
+fn c_ident(prefix : &str, name : &str) -> String {
+    let mut ident = String::from(prefix);
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            ident.push(c);
+        } else {
+            let _ = write!(ident, "_{:x}_", c as u32);
+        }
+    }
+    ident
+}
+
+/// Returns the c representation of a top-level item with this name.
+fn c_item(name : &str) -> String {
+    c_ident("cosy_", name)
+}
+
+/// Returns the c representation of a struct field with this name.
+fn c_field(name : &str) -> String {
+    c_ident("f_", name)
+}
+
+/// Returns a C string literal containing these bytes. Bytes which are not printable ASCII
+/// characters are written as octal escape sequences.
+fn c_string(bytes : &[u8]) -> String {
+    let mut string = String::from("\"");
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' | b'?' => {
+                let _ = write!(string, "\\{}", *byte as char);
+            },
+            b' '..=b'~' => string.push(*byte as char),
+            _ => {
+                let _ = write!(string, "\\{:03o}", byte);
+            }
+        }
+    }
+    string.push('"');
+    string
+}
+
+/// Returns the suffix of the helper functions defined for integers of this type.
+fn c_int_suffix(ty : &ir::TypeKind) -> Option<String> {
+    match ty {
+        ir::TypeKind::Int(n) => Some(format!("i{}", n)),
+        ir::TypeKind::UInt(n) => Some(format!("u{}", n)),
+        _ => None
+    }
+}
+
+/// Returns the name of the helper function which applies this operator to integers of this
+/// type, for operators which abort the program on overflow or on an invalid operand.
+fn c_checked_op(op : ir::BuiltinOp, ty : &ir::TypeKind) -> Option<String> {
+    use ir::BuiltinOp as Op;
+    let name = match op {
+        Op::Add => "add",
+        Op::Sub => "sub",
+        Op::Mul => "mul",
+        Op::Div => "div",
+        Op::Rem => "rem",
+        Op::Shl => "shl",
+        Op::Shr => "shr",
+        Op::Neg => "neg",
+        _ => return None
+    };
+    c_int_suffix(ty).map(|suffix| format!("builtin_{}_{}", name, suffix))
+}
+
+/// Defines the checked integer operators for the type `T`, whose helper functions are named
+/// using the suffix `S`. Signed division by -1 is checked, since it overflows for `MIN`.
+/// Conversions from floating point numbers saturate at `MIN` and `MAX`, and convert NaN into zero.
+const CHECKED_INT_MACRO : &str = r#"#define DEFINE_CHECKED_INT(T, S, SIGNED, MIN, MAX) \
+static inline T builtin_add_##S(T x, T y) { T r; if (__builtin_add_overflow(x, y, &r)) __builtin_trap(); return r; } \
+static inline T builtin_sub_##S(T x, T y) { T r; if (__builtin_sub_overflow(x, y, &r)) __builtin_trap(); return r; } \
+static inline T builtin_mul_##S(T x, T y) { T r; if (__builtin_mul_overflow(x, y, &r)) __builtin_trap(); return r; } \
+static inline T builtin_neg_##S(T x) { T r; if (__builtin_sub_overflow(0, x, &r)) __builtin_trap(); return r; } \
+static inline T builtin_div_##S(T x, T y) { if (y == 0 || (SIGNED && x == MIN && y == (T) -1)) __builtin_trap(); return x / y; } \
+static inline T builtin_rem_##S(T x, T y) { if (y == 0 || (SIGNED && x == MIN && y == (T) -1)) __builtin_trap(); return x % y; } \
+static inline T builtin_shl_##S(T x, T y) { if ((uint64_t) y >= sizeof(T) * 8) __builtin_trap(); return (T) ((uint64_t) x << y); } \
+static inline T builtin_shr_##S(T x, T y) { if ((uint64_t) y >= sizeof(T) * 8) __builtin_trap(); return x >> y; } \
+static inline T builtin_from_float_##S(double x) { return x != x ? 0 : x <= (double) MIN ? MIN : x >= (double) MAX ? MAX : (T) x; }"#;
+
 const INDENTATION : &'static str = "  ";
 
 /// Manages generation of code from IR.
@@ -19,7 +103,10 @@ pub struct Codegen<'a, W : Write> {
     out : W,
     next_local : usize,
     indent : usize,
-    newline : bool
+    newline : bool,
+    locals : HashMap<usize, usize>,
+    mutable : HashSet<usize>,
+    items : Vec<String>
 }
 
 impl<W : Write> Failable for Codegen<'_, W> {
@@ -40,7 +127,10 @@ impl<'a, W : Write> Codegen<'a, W> {
         let next_local = 0;
         let indent = 0;
         let newline = true;
-        Self { src, issues, out, next_local, indent, newline }
+        let locals = HashMap::new();
+        let mutable = HashSet::new();
+        let items = Vec::new();
+        Self { src, issues, out, next_local, indent, newline, locals, mutable, items }
     }
 
     /// Returns the next local id.
@@ -83,46 +173,397 @@ impl<'a, W : Write> Codegen<'a, W> {
         Some(())
     }
 
-    /// Consumes this code generator and writes the C code for this IR instruction.
-    pub fn gen_c(mut self, inst : ir::Inst) -> Option<()> {
+    /// Consumes this code generator and writes the C code for this module. The generated
+    /// `main` function prints the value returned by the `main` item.
+    pub fn gen_c(mut self, module : ir::Module) -> Option<()> {
+        let src = self.src;
+        let main = if let Some(id) = module.find_item("main", src) {
+            id
+        } else {
+            return self.report(CompilerError::unreachable("program without a `main` function"));
+        };
+        let unsigned = matches!(module.items[main].kind, ir::ItemKind::Function {
+                return_type : ir::InstType { kind : ir::TypeKind::UInt(_), .. }, .. });
+        self.items = module.items.iter()
+                .map(|item| c_item(&item.qualified_name(src)))
+                .collect();
         self.writeln("#include <stdio.h>")?;
         self.writeln("#include <stdint.h>")?;
+        self.writeln("#include <stdbool.h>")?;
         self.writeln("struct Empty { };")?;
+        self.writeln("struct Str { const uint8_t* data; uint64_t len; };")?;
+        self.writeln("static struct Empty builtin_print(struct Str s) {")?;
+        self.indent();
+        self.writeln("fwrite(s.data, 1, s.len, stdout);")?;
+        self.writeln("return (struct Empty) { };")?;
+        self.unindent();
+        self.writeln("}")?;
+        self.writeln(CHECKED_INT_MACRO)?;
+        for bits in [8, 16, 32, 64] {
+            self.writeln(format!("DEFINE_CHECKED_INT(int{0}_t, i{0}, 1, INT{0}_MIN, INT{0}_MAX)", bits))?;
+            self.writeln(format!("DEFINE_CHECKED_INT(uint{0}_t, u{0}, 0, 0, UINT{0}_MAX)", bits))?;
+        }
+        for (id, item) in module.items.iter().enumerate() {
+            if let ir::ItemKind::Struct { .. } = item.kind {
+                self.write("struct ")?;
+                self.write(self.items[id].clone())?;
+                self.writeln(";")?;
+            }
+        }
+        let mut defined = Vec::new();
+        for id in 0..module.items.len() {
+            self.visit_c_struct(id, &module.items, &mut defined)?;
+        }
+        for (id, item) in module.items.iter().enumerate() {
+            if self.visit_c_header(id, item, false)? {
+                self.writeln(";")?;
+            }
+        }
+        for (id, item) in module.items.into_iter().enumerate() {
+            self.visit_c_item(id, item)?;
+        }
         self.writeln("int main() {")?;
         self.indent();
-        let local = self.visit_c_inst(inst)?;
-        self.write(r#"printf("%jd\n", (intmax_t)"#)?;
-        self.write(c_local(local))?;
-        self.writeln(r#");"#)?;
+        self.write(if unsigned {
+            r#"printf("%ju\n", (uintmax_t) "#
+        } else {
+            r#"printf("%jd\n", (intmax_t) "#
+        })?;
+        self.write(self.items[main].clone())?;
+        self.writeln(r#"());"#)?;
         self.writeln("return 0;")?;
         self.unindent();
-        self.write("}")
+        self.writeln("}")
+    }
+
+    /// Writes the definition of the struct with this id, after any structs it contains.
+    fn visit_c_struct(&mut self, id : usize, items : &[ir::Item], defined : &mut Vec<usize>) -> Option<()> {
+        let fields = match &items[id].kind {
+            ir::ItemKind::Struct { fields } if !defined.contains(&id) => fields,
+            _ => return Some(())
+        };
+        defined.push(id);
+        for field in fields {
+            if let ir::TypeKind::Struct { id : inner, .. } = field.datatype.kind {
+                self.visit_c_struct(inner, items, defined)?;
+            }
+        }
+        self.write("struct ")?;
+        self.write(self.items[id].clone())?;
+        self.writeln(" {")?;
+        self.indent();
+        for field in fields {
+            self.visit_c_type(&field.datatype)?;
+            self.write(" ")?;
+            self.write(c_field(self.render(&field.name)))?;
+            self.writeln(";")?;
+        }
+        self.unindent();
+        self.writeln("};")
+    }
+
+    /// Writes the C function header for this item, and returns whether the item has one.
+    /// If `named` is true, then each parameter is also assigned a new local variable.
+    fn visit_c_header(&mut self, id : usize, item : &ir::Item, named : bool) -> Option<bool> {
+        let (params, return_type) = match &item.kind {
+            ir::ItemKind::Function { params, return_type, .. } => (params.as_slice(), return_type),
+            ir::ItemKind::Definition { value } => (&[][..], &value.datatype),
+            ir::ItemKind::Data { .. } | ir::ItemKind::Struct { .. } => return Some(false)
+        };
+        self.visit_c_type(return_type)?;
+        self.write(" ")?;
+        self.write(self.items[id].clone())?;
+        self.write("(")?;
+        if params.is_empty() {
+            self.write("void")?;
+        }
+        for (i, param) in params.iter().enumerate() {
+            if i != 0 {
+                self.write(", ")?;
+            }
+            self.visit_c_type(&param.datatype)?;
+            if named {
+                let local = self.get_next_local();
+                self.locals.insert(param.id, local);
+                self.write(" ")?;
+                self.write(c_local(local))?;
+            }
+        }
+        self.write(")")?;
+        Some(true)
+    }
+
+    fn visit_c_item(&mut self, id : usize, item : ir::Item) -> Option<()> {
+        self.next_local = 0;
+        self.locals.clear();
+        self.mutable.clear();
+        if !self.visit_c_header(id, &item, true)? {
+            return Some(());
+        }
+        let body = match item.kind {
+            ir::ItemKind::Function { body, .. } => body,
+            ir::ItemKind::Definition { value } => value,
+            ir::ItemKind::Data { .. } | ir::ItemKind::Struct { .. } => return Some(())
+        };
+        self.writeln(" {")?;
+        self.indent();
+        let returns = body.datatype.kind != ir::TypeKind::Void;
+        let local = self.visit_c_inst(body)?;
+        if returns {
+            self.write("return ")?;
+            self.write(c_local(local))?;
+            self.writeln(";")?;
+        }
+        self.unindent();
+        self.writeln("}")
     }
 
-    fn visit_c_type(&mut self, ty : ir::InstType) -> Option<()> {
-        let span = ty.span;
-        match ty.kind {
+    fn visit_c_type(&mut self, ty : &ir::InstType) -> Option<()> {
+        self.visit_c_type_kind(&ty.kind, &ty.span)
+    }
+
+    fn visit_c_type_kind(&mut self, ty : &ir::TypeKind, span : &Span) -> Option<()> {
+        match ty {
             ir::TypeKind::Void => self.write("void"),
             ir::TypeKind::Empty => self.write("struct Empty"),
+            ir::TypeKind::Bool => self.write("bool"),
             ir::TypeKind::Int(n) => self.write(format!("int{}_t", n)),
             ir::TypeKind::UInt(n) => self.write(format!("uint{}_t", n)),
+            ir::TypeKind::Float(32) => self.write("float"),
+            ir::TypeKind::Float(_) => self.write("double"),
+            ir::TypeKind::Char => self.write("uint32_t"),
+            ir::TypeKind::Str => self.write("struct Str"),
+            ir::TypeKind::Struct { id, .. } => self.write(format!("struct {}", self.items[*id])),
+            ir::TypeKind::Pointer(inner) => {
+                self.visit_c_type_kind(inner, span)?;
+                self.write("*")
+            },
+            ir::TypeKind::Tuple(_) => self.report(CompilerError::unimplemented("tuple types").span(span))?,
+            ir::TypeKind::Array { .. } => self.report(CompilerError::unimplemented("array types").span(span))?,
+            ir::TypeKind::Function { .. } => self.report(CompilerError::unimplemented("function types").span(span))?,
             ir::TypeKind::Infer
-                | ir::TypeKind::Variable => self.report(
-                    CompilerError::unreachable("untyped").span(&span))?
+                | ir::TypeKind::Unknown { .. }
+                | ir::TypeKind::Variable { .. } => self.report(
+                    CompilerError::unreachable("untyped").span(span))?
+        }
+    }
+
+    /// Declares a new local variable of this type, without initialising it. Values of type
+    /// `void` are never stored, so their locals are not declared.
+    fn declare_c_local(&mut self, ty : &ir::InstType) -> Option<usize> {
+        let local = self.get_next_local();
+        if ty.kind != ir::TypeKind::Void {
+            self.visit_c_type(ty)?;
+            self.write(" ")?;
+            self.write(c_local(local))?;
+            self.writeln(";")?;
         }
+        Some(local)
+    }
+
+    /// Assigns the value of an instruction to a local declared using `declare_c_local`.
+    fn visit_c_assign(&mut self, local : usize, inst : ir::Inst) -> Option<()> {
+        let returns = inst.datatype.kind != ir::TypeKind::Void;
+        let value = self.visit_c_inst(inst)?;
+        if returns {
+            self.write(c_local(local))?;
+            self.write(" = ")?;
+            self.write(c_local(value))?;
+            self.writeln(";")?;
+        }
+        Some(())
+    }
+
+    /// Returns the C lvalue for this instruction, so that it can be assigned to or have its
+    /// address taken. Instructions which are not variables, fields or dereferences are stored
+    /// in a new local.
+    fn visit_c_place(&mut self, inst : ir::Inst) -> Option<String> {
+        let ir::Inst { span, datatype, kind } = inst;
+        let place = match kind {
+            ir::InstKind::Local { id } => match self.locals.get(&id) {
+                Some(local) if self.mutable.contains(&id) => c_local(*local),
+                // immutable variables are copied, so that writes through a pointer to them are not observed
+                Some(_) => {
+                    let local = self.declare_c_local(&datatype)?;
+                    self.visit_c_assign(local, ir::Inst::new_typed(span, kind, datatype))?;
+                    c_local(local)
+                },
+                None => self.report(CompilerError::unreachable("unbound local variable").span(&span))?
+            },
+            ir::InstKind::Field { value, name, .. } => {
+                let place = self.visit_c_place(*value)?;
+                format!("{}.{}", place, c_field(self.render(&name)))
+            },
+            ir::InstKind::Dereference { value } => format!("(*{})", c_local(self.visit_c_inst(*value)?)),
+            kind => c_local(self.visit_c_inst(ir::Inst::new_typed(span, kind, datatype))?)
+        };
+        Some(place)
     }
 
     fn visit_c_inst(&mut self, inst : ir::Inst) -> Option<usize> {
         let span = inst.span;
         let rvalue = match inst.kind {
             ir::InstKind::Variable => self.report(
-                    CompilerError::unimplemented("variables").span(&span))?,
-            ir::InstKind::Integral { .. } => self.render(&span).to_string(),
-            ir::InstKind::FunctionApp { .. } => self.report(
-                    CompilerError::unimplemented("function application").span(&span))?
+                    CompilerError::unreachable("unresolved variable").span(&span))?,
+            ir::InstKind::Local { id } => match self.locals.get(&id) {
+                Some(local) if self.mutable.contains(&id) => c_local(*local),
+                Some(local) => return Some(*local),
+                None => self.report(CompilerError::unreachable("unbound local variable").span(&span))?
+            },
+            ir::InstKind::Block { stmts, value } => {
+                let local = self.declare_c_local(&inst.datatype)?;
+                self.writeln("{")?;
+                self.indent();
+                for stmt in stmts {
+                    self.visit_c_inst(stmt)?;
+                }
+                if let Some(value) = value {
+                    self.visit_c_assign(local, *value)?;
+                }
+                self.unindent();
+                self.writeln("}")?;
+                return Some(local);
+            },
+            ir::InstKind::If { condition, on_true, on_false } => {
+                let condition = self.visit_c_inst(*condition)?;
+                let local = self.declare_c_local(&inst.datatype)?;
+                self.write("if (")?;
+                self.write(c_local(condition))?;
+                self.writeln(") {")?;
+                self.indent();
+                self.visit_c_assign(local, *on_true)?;
+                self.unindent();
+                self.writeln("} else {")?;
+                self.indent();
+                self.visit_c_assign(local, *on_false)?;
+                self.unindent();
+                self.writeln("}")?;
+                return Some(local);
+            },
+            ir::InstKind::Assign { target, value } => {
+                let value = self.visit_c_inst(*value)?;
+                let place = self.visit_c_place(*target)?;
+                self.write(place)?;
+                self.write(" = ")?;
+                self.write(c_local(value))?;
+                self.writeln(";")?;
+                return self.declare_c_local(&inst.datatype);
+            },
+            ir::InstKind::Loop { body } => {
+                let local = self.declare_c_local(&inst.datatype)?;
+                self.writeln("for (;;) {")?;
+                self.indent();
+                self.visit_c_inst(*body)?;
+                self.unindent();
+                self.writeln("}")?;
+                return Some(local);
+            },
+            ir::InstKind::Break => "break".to_string(),
+            ir::InstKind::Continue => "continue".to_string(),
+            ir::InstKind::Let { id, mutable, value, body, .. } => {
+                let local = if mutable {
+                    let local = self.declare_c_local(&value.datatype)?;
+                    self.visit_c_assign(local, *value)?;
+                    self.mutable.insert(id);
+                    local
+                } else {
+                    self.visit_c_inst(*value)?
+                };
+                self.locals.insert(id, local);
+                return self.visit_c_inst(*body);
+            },
+            ir::InstKind::Integral { value } => match inst.datatype.kind {
+                ir::TypeKind::UInt(_) => format!("{}u", value),
+                // the literal `9223372036854775808` has no signed type in C, so it cannot be negated
+                _ if value == i64::MIN as i128 => "INT64_MIN".to_string(),
+                _ => value.to_string()
+            },
+            ir::InstKind::Boolean { value } => value.to_string(),
+            ir::InstKind::Float { value } => if let ir::TypeKind::Float(32) = inst.datatype.kind {
+                format!("{:?}f", value)
+            } else {
+                format!("{:?}", value)
+            },
+            ir::InstKind::Char { value } => format!("{}u", value as u32),
+            ir::InstKind::String { value } => {
+                format!("(struct Str) {{ (const uint8_t*) {}, {} }}", c_string(&value), value.len())
+            },
+            ir::InstKind::Struct { fields } => {
+                // fields are evaluated in the order they are written, then initialised in declaration order
+                let mut locals = Vec::new();
+                for (_, index, value) in fields {
+                    locals.push((index, c_local(self.visit_c_inst(value)?)));
+                }
+                locals.sort_by_key(|(index, _)| *index);
+                let locals = locals.into_iter()
+                        .map(|(_, local)| local)
+                        .collect::<Vec<_>>();
+                format!("{{ {} }}", locals.join(", "))
+            },
+            ir::InstKind::Field { value, name, .. } => {
+                let local = self.visit_c_inst(*value)?;
+                format!("{}.{}", c_local(local), c_field(self.render(&name)))
+            },
+            ir::InstKind::Reference { value } => format!("&{}", self.visit_c_place(*value)?),
+            ir::InstKind::Cast { value, .. } => {
+                let from = value.datatype.kind.clone();
+                let value = self.visit_c_inst(*value)?;
+                if from == inst.datatype.kind {
+                    return Some(value);
+                }
+                if let (ir::TypeKind::Float(_), Some(suffix)) = (from, c_int_suffix(&inst.datatype.kind)) {
+                    format!("builtin_from_float_{}({})", suffix, c_local(value))
+                } else {
+                    let local = self.get_next_local();
+                    self.visit_c_type(&inst.datatype)?;
+                    self.write(format!(" {} = (", c_local(local)))?;
+                    self.visit_c_type(&inst.datatype)?;
+                    self.writeln(format!(") {};", c_local(value)))?;
+                    return Some(local);
+                }
+            },
+            ir::InstKind::Dereference { value } => format!("*{}", c_local(self.visit_c_inst(*value)?)),
+            ir::InstKind::Global { id } => format!("{}()", self.items[id]),
+            ir::InstKind::Associated { .. } | ir::InstKind::Method { .. } => self.report(
+                    CompilerError::unreachable("unresolved associated item").span(&span))?,
+            ir::InstKind::Builtin { .. } => self.report(
+                    CompilerError::unimplemented("first-class operators").span(&span))?,
+            ir::InstKind::FunctionApp { callsite, args } => {
+                let checked = match (&callsite.kind, args.first()) {
+                    (ir::InstKind::Builtin { op }, Some(arg)) => c_checked_op(*op, &arg.datatype.kind),
+                    _ => None
+                };
+                let mut locals = Vec::new();
+                for arg in args {
+                    locals.push(c_local(self.visit_c_inst(arg)?));
+                }
+                match (callsite.kind, checked, locals.as_slice()) {
+                    (_, Some(helper), _) => format!("{}({})", helper, locals.join(", ")),
+                    (ir::InstKind::Builtin { op : ir::BuiltinOp::Print }, _, [value]) => format!("builtin_print({})", value),
+                    // the conversion happens when the value is assigned to a local of the result type
+                    (ir::InstKind::Builtin { op : ir::BuiltinOp::ToFloat }, _, [value]) => value.clone(),
+                    // conversions into integers saturate, so they use the helper for the result type
+                    (ir::InstKind::Builtin { op : ir::BuiltinOp::ToInt }, _, [value]) => match c_int_suffix(&inst.datatype.kind) {
+                        Some(suffix) => format!("builtin_from_float_{}({})", suffix, value),
+                        None => self.report(CompilerError::unreachable(
+                                format!("conversion of a float into `{}`", inst.datatype.kind)).span(&span))?
+                    },
+                    (ir::InstKind::Builtin { op }, _, [value]) => format!("{}{}", op, value),
+                    (ir::InstKind::Builtin { op }, _, [left, right]) => format!("{} {} {}", left, op, right),
+                    (ir::InstKind::Builtin { op }, _, _) => self.report(CompilerError::unreachable(
+                            format!("application of `{}` to {} argument(s)", op, locals.len())).span(&span))?,
+                    (ir::InstKind::Global { id }, _, _) => format!("{}({})", self.items[id], locals.join(", ")),
+                    _ => self.report(CompilerError::unimplemented("first-class functions").span(&callsite.span))?
+                }
+            }
         };
         let local = self.get_next_local();
-        self.visit_c_type(inst.datatype)?;
+        if inst.datatype.kind == ir::TypeKind::Void {
+            self.write(rvalue)?;
+            self.writeln(";")?;
+            return Some(local);
+        }
+        self.visit_c_type(&inst.datatype)?;
         self.write(" ")?;
         self.write(c_local(local))?;
         self.write(" = ")?;
@@ -132,7 +573,7 @@ impl<'a, W : Write> Codegen<'a, W> {
     }
 }
 
-/// Generates C code from this IR instruction.
-pub fn generate_c<W : Write>(inst : ir::Inst, src : &str, issues : &mut IssueTracker, out : W) -> Option<()> {
-    Codegen::new(src, issues, out).gen_c(inst)
+/// Generates C code from this IR module.
+pub fn generate_c<W : Write>(module : ir::Module, src : &str, issues : &mut IssueTracker, out : W) -> Option<()> {
+    Codegen::new(src, issues, out).gen_c(module)
 }
@@ -1,9 +1,63 @@
 use libcosyc_diagnostic::{
     error::{ CompilerError, IssueTracker, Failable },
-    source::Renderable
+    source::{ Span, Renderable }
 };
 use libcosyc_ir::ir;
-use inkwell::{ context::Context, builder::Builder, module::Module };
+use inkwell::{
+    AddressSpace,
+    IntPredicate,
+    FloatPredicate,
+    OptimizationLevel,
+    context::Context,
+    builder::Builder,
+    module::{ Module, Linkage },
+    intrinsics::Intrinsic,
+    basic_block::BasicBlock,
+    execution_engine::JitFunction,
+    passes::{ PassManager, PassManagerBuilder },
+    targets::{ CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine },
+    types::{ IntType, StructType, BasicType, BasicTypeEnum, BasicMetadataTypeEnum },
+    values::{ IntValue, PointerValue, StructValue, FunctionValue, BasicValue, BasicValueEnum, BasicMetadataValueEnum }
+};
+use std::{ path::Path, collections::HashMap };
+
+/// The name of the entrypoint generated for programs that are run using the JIT.
+const JIT_ENTRY : &str = "cosy.jit.entry";
+
+/// The type signature of the JIT entrypoint.
+type JitEntry = unsafe extern "C" fn() -> u64;
+
+/// Returns the symbol name of a top-level item with this name.
+fn symbol_name(name : &str) -> String {
+    format!("cosy.{}", name)
+}
+
+/// Returns whether this item is a function which returns a signed integer.
+fn returns_signed(item : &ir::Item) -> bool {
+    matches!(item.kind, ir::ItemKind::Function {
+            return_type : ir::InstType { kind : ir::TypeKind::Int(_), .. }, .. })
+}
+
+/// Represents the kinds of file the LLVM backend can write.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum FileKind {
+    /// A native object file.
+    Object,
+    /// A native assembly file.
+    Assembly,
+    /// An LLVM bitcode file.
+    Bitcode
+}
+
+/// Converts an optimisation level from 0 to 3 into its respective LLVM optimisation level.
+fn optimization_level(level : u8) -> OptimizationLevel {
+    match level {
+        0 => OptimizationLevel::None,
+        1 => OptimizationLevel::Less,
+        2 => OptimizationLevel::Default,
+        _ => OptimizationLevel::Aggressive
+    }
+}
 
 /// Manages generation of code from IR.
 pub struct Codegen<'a, 'ctx> {
@@ -12,6 +66,11 @@ pub struct Codegen<'a, 'ctx> {
     context : &'ctx Context,
     module : Module<'ctx>,
     builder : Builder<'ctx>,
+    locals : HashMap<usize, BasicValueEnum<'ctx>>,
+    variables : HashMap<usize, PointerValue<'ctx>>,
+    loops : Vec<(BasicBlock<'ctx>, BasicBlock<'ctx>)>,
+    functions : HashMap<usize, FunctionValue<'ctx>>,
+    structs : HashMap<usize, StructType<'ctx>>
 }
 
 impl Failable for Codegen<'_, '_> {
@@ -31,29 +90,860 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> {
     pub fn new(context : &'ctx Context, module_name : &str, src : &'a str, issues : &'a mut IssueTracker) -> Self {
         let module = context.create_module(module_name);
         let builder = context.create_builder();
-        Self { src, issues, context, module, builder }
+        let locals = HashMap::new();
+        let variables = HashMap::new();
+        let loops = Vec::new();
+        let functions = HashMap::new();
+        let structs = HashMap::new();
+        Self { src, issues, context, module, builder, locals, variables, loops, functions, structs }
+    }
+
+    /// Generates the items of this module, and a `main` entrypoint which prints the value of
+    /// the `main` item, in the same way as the C backend.
+    pub fn generate_main(&mut self, module : ir::Module) -> Option<()> {
+        let i32_type = self.context.i32_type();
+        let (value, signed) = self.generate_entry(module, "main", i32_type)?;
+        let format = self.build_global_bytes(if signed { b"%lld\n\0" } else { b"%llu\n\0" });
+        let printf = if let Some(function) = self.module.get_function("printf") {
+            function
+        } else {
+            let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+            let fn_type = i32_type.fn_type(&[ptr_type.into()], true);
+            self.module.add_function("printf", fn_type, Some(Linkage::External))
+        };
+        self.builder.build_call(printf, &[format.into(), value.into()], "");
+        self.builder.build_return(Some(&i32_type.const_int(0, false)));
+        Some(())
+    }
+
+    /// Generates the items of this module, and a function with no parameters which returns
+    /// the value of the `main` item, resized to fit the return type.
+    pub fn generate_module(&mut self, module : ir::Module, name : &str, ret : IntType<'ctx>) -> Option<()> {
+        let (value, signed) = self.generate_entry(module, name, ret)?;
+        let value = self.build_int_resize(value, ret, signed);
+        self.builder.build_return(Some(&value));
+        Some(())
+    }
+
+    /// Generates the items of this module, and the body of a function with no parameters which
+    /// calls the `main` item. Returns the value of this call, extended to 64 bits, and whether it
+    /// is signed. The function is left without a return.
+    fn generate_entry(&mut self, module : ir::Module, name : &str, ret : IntType<'ctx>) -> Option<(IntValue<'ctx>, bool)> {
+        let main = if let Some(id) = module.find_item("main", self.src) {
+            id
+        } else {
+            return self.report(CompilerError::unreachable("program without a `main` function"));
+        };
+        let signed = returns_signed(&module.items[main]);
+        for (id, item) in module.items.iter().enumerate() {
+            if let ir::ItemKind::Struct { .. } = item.kind {
+                let name = symbol_name(item.name.render(self.src));
+                self.structs.insert(id, self.context.opaque_struct_type(&name));
+            }
+        }
+        for (id, item) in module.items.iter().enumerate() {
+            self.declare_item(id, item)?;
+        }
+        for (id, item) in module.items.into_iter().enumerate() {
+            self.generate_item(id, item)?;
+        }
+        let fn_type = ret.fn_type(&[], false);
+        let entry_fn = self.module.add_function(name, fn_type, None);
+        let entry_block = self.context.append_basic_block(entry_fn, "entry");
+        self.builder.position_at_end(entry_block);
+        let value = self.build_item_call(main, Vec::new(), &Span::default())?;
+        let value = self.build_int_resize(value.into_int_value(), self.context.i64_type(), signed);
+        Some((value, signed))
+    }
+
+    /// Declares the function or struct type for this item, so that it can be used before it
+    /// is generated.
+    fn declare_item(&mut self, id : usize, item : &ir::Item) -> Option<()> {
+        let (params, return_type) = match &item.kind {
+            ir::ItemKind::Function { params, return_type, .. } => (params.as_slice(), return_type),
+            ir::ItemKind::Definition { value } => (&[][..], &value.datatype),
+            ir::ItemKind::Struct { fields } => {
+                let mut field_types = Vec::new();
+                for field in fields {
+                    field_types.push(self.visit_llvm_type(&field.datatype)?);
+                }
+                self.structs[&id].set_body(&field_types, false);
+                return Some(());
+            },
+            ir::ItemKind::Data { .. } => return Some(())
+        };
+        let mut param_types = Vec::new();
+        for param in params {
+            param_types.push(BasicMetadataTypeEnum::from(self.visit_llvm_type(&param.datatype)?));
+        }
+        let fn_type = self.visit_llvm_type(return_type)?.fn_type(&param_types, false);
+        let name = symbol_name(&item.qualified_name(self.src));
+        let function = self.module.add_function(&name, fn_type, None);
+        self.functions.insert(id, function);
+        Some(())
+    }
+
+    /// Generates the body of the function declared for this item.
+    fn generate_item(&mut self, id : usize, item : ir::Item) -> Option<()> {
+        let (params, body) = match item.kind {
+            ir::ItemKind::Function { params, body, .. } => (params, body),
+            ir::ItemKind::Definition { value } => (Vec::new(), value),
+            ir::ItemKind::Data { .. } | ir::ItemKind::Struct { .. } => return Some(())
+        };
+        let function = self.functions[&id];
+        let block = self.context.append_basic_block(function, "entry");
+        self.builder.position_at_end(block);
+        self.locals.clear();
+        self.variables.clear();
+        for (i, param) in params.iter().enumerate() {
+            if let Some(value) = function.get_nth_param(i as u32) {
+                self.locals.insert(param.id, value);
+            }
+        }
+        let value = self.visit_llvm_inst(body)?;
+        self.builder.build_return(Some(&value));
+        Some(())
     }
 
-    /// Generates the `main` entrypoint.
-    pub fn generate_main(&self) {
-        let void_type = self.context.void_type();
-        let fn_type = void_type.fn_type(&[], false);
-        let main_fn = self.module.add_function("main", fn_type, None);
-        let main_block = self.context.append_basic_block(main_fn, "entry");
-        self.builder.position_at_end(main_block);
+    /// Builds a call to the function declared for this item.
+    fn build_item_call(&mut self, id : usize, args : Vec<BasicValueEnum<'ctx>>, span : &Span) -> Option<BasicValueEnum<'ctx>> {
+        let function = if let Some(function) = self.functions.get(&id) {
+            *function
+        } else {
+            return self.report(CompilerError::unreachable("undeclared function").span(span));
+        };
+        let args = args.into_iter()
+                .map(BasicMetadataValueEnum::from)
+                .collect::<Vec<_>>();
+        let call = self.builder.build_call(function, &args, "");
+        if let Some(value) = call.try_as_basic_value().left() {
+            Some(value)
+        } else {
+            self.report(CompilerError::unimplemented("functions returning `()`").span(span))
+        }
+    }
+
+    /// Builds a pointer to these bytes, which are stored in a private global.
+    fn build_global_bytes(&mut self, value : &[u8]) -> PointerValue<'ctx> {
+        let byte_type = self.context.i8_type();
+        let bytes = value.iter()
+                .map(|byte| byte_type.const_int(*byte as u64, false))
+                .collect::<Vec<_>>();
+        let data = byte_type.const_array(&bytes);
+        let global = self.module.add_global(data.get_type(), None, "str");
+        global.set_initializer(&data);
+        global.set_constant(true);
+        global.set_linkage(Linkage::Private);
+        global.as_pointer_value().const_cast(byte_type.ptr_type(AddressSpace::default()))
+    }
+
+    /// Builds a constant string whose bytes are stored in a private global.
+    fn build_string(&mut self, value : &[u8], ty : StructType<'ctx>) -> BasicValueEnum<'ctx> {
+        let ptr = self.build_global_bytes(value);
+        let len = self.context.i64_type().const_int(value.len() as u64, false);
+        ty.const_named_struct(&[ptr.into(), len.into()]).into()
+    }
+
+    /// Builds a call to `write`, which writes this string to the standard output.
+    fn build_print(&mut self, value : StructValue<'ctx>, span : &Span) -> Option<BasicValueEnum<'ctx>> {
+        let len_type = self.context.i64_type();
+        let write = if let Some(function) = self.module.get_function("write") {
+            function
+        } else {
+            let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+            let fn_type = len_type.fn_type(&[
+                    self.context.i32_type().into(), ptr_type.into(), len_type.into()], false);
+            self.module.add_function("write", fn_type, Some(Linkage::External))
+        };
+        let (data, len) = match (self.builder.build_extract_value(value, 0, ""),
+                self.builder.build_extract_value(value, 1, "")) {
+            (Some(data), Some(len)) => (data, len),
+            _ => return self.report(CompilerError::unreachable("malformed string").span(span))
+        };
+        let stdout = self.context.i32_type().const_int(1, false);
+        self.builder.build_call(write, &[stdout.into(), data.into(), len.into()], "");
+        Some(self.context.struct_type(&[], false).get_undef().into())
+    }
+
+    /// Compiles the module using the JIT and calls its entrypoint, returning the result.
+    /// The entrypoint must have been generated using `JIT_ENTRY`.
+    pub fn run_jit(&mut self, level : u8) -> Option<u64> {
+        if let Err(e) = Target::initialize_native(&InitializationConfig::default()) {
+            return self.report(CompilerError::new()
+                    .reason("unable to initialise the native target")
+                    .note(e));
+        }
+        let engine = match self.module.create_jit_execution_engine(optimization_level(level)) {
+            Ok(engine) => engine,
+            Err(e) => return self.report(CompilerError::new()
+                    .reason("unable to create the JIT execution engine")
+                    .note(e.to_string()))
+        };
+        let entry : Result<JitFunction<JitEntry>, _> = unsafe { engine.get_function(JIT_ENTRY) };
+        match entry {
+            Ok(entry) => Some(unsafe { entry.call() }),
+            Err(e) => self.report(CompilerError::bug()
+                    .reason("unable to find the JIT entrypoint")
+                    .note(format!("{:?}", e)))
+        }
+    }
+
+    /// Verifies the generated module, and reports an error if it is malformed.
+    pub fn verify(&mut self) -> Option<()> {
+        if let Err(e) = self.module.verify() {
+            self.report(CompilerError::bug()
+                    .reason("the LLVM backend generated an invalid module")
+                    .note(e.to_string()))
+        } else {
+            Some(())
+        }
+    }
+
+    /// Runs the LLVM optimisation passes for this optimisation level over the module.
+    pub fn optimize(&self, level : u8) {
+        let pm_builder = PassManagerBuilder::create();
+        pm_builder.set_optimization_level(optimization_level(level));
+        let pm = PassManager::create(());
+        pm_builder.populate_module_pass_manager(&pm);
+        pm.run_on(&self.module);
+    }
+
+    /// Creates a target machine for the host, and configures the module to use it.
+    pub fn create_host_machine(&mut self, level : u8) -> Option<TargetMachine> {
+        if let Err(e) = Target::initialize_native(&InitializationConfig::default()) {
+            return self.report(CompilerError::new()
+                    .reason("unable to initialise the native target")
+                    .note(e));
+        }
+        let triple = TargetMachine::get_default_triple();
+        let target = match Target::from_triple(&triple) {
+            Ok(target) => target,
+            Err(e) => return self.report(CompilerError::new()
+                    .reason(format!("unsupported target triple `{}`", triple.as_str().to_string_lossy()))
+                    .note(e.to_string()))
+        };
+        let cpu = TargetMachine::get_host_cpu_name().to_string();
+        let features = TargetMachine::get_host_cpu_features().to_string();
+        let machine = target.create_target_machine(&triple, &cpu, &features,
+                optimization_level(level), RelocMode::PIC, CodeModel::Default);
+        if let Some(machine) = machine {
+            self.module.set_triple(&triple);
+            self.module.set_data_layout(&machine.get_target_data().get_data_layout());
+            Some(machine)
+        } else {
+            self.report(CompilerError::new()
+                    .reason(format!("unable to create a target machine for `{}`", triple.as_str().to_string_lossy())))
+        }
+    }
+
+    /// Writes the module to a file of this kind.
+    pub fn write_to_file(&mut self, kind : FileKind, path : &str, level : u8) -> Option<()> {
+        let machine = self.create_host_machine(level)?;
+        let result = match kind {
+            FileKind::Object => machine
+                    .write_to_file(&self.module, FileType::Object, Path::new(path))
+                    .map_err(|e| e.to_string()),
+            FileKind::Assembly => machine
+                    .write_to_file(&self.module, FileType::Assembly, Path::new(path))
+                    .map_err(|e| e.to_string()),
+            FileKind::Bitcode => if self.module.write_bitcode_to_path(Path::new(path)) {
+                Ok(())
+            } else {
+                Err("failed to write bitcode".to_string())
+            }
+        };
+        if let Err(e) = result {
+            self.report(CompilerError::new()
+                    .reason(format!("unable to write to a file with the name `{}`", path))
+                    .note(e))
+        } else {
+            Some(())
+        }
     }
 
     /// Emits LLVM IR to stderr.
     pub fn print_ir_to_stderr(&self) {
         self.module.print_to_stderr();
     }
+
+    /// Returns the LLVM IR of this module as a string.
+    pub fn print_ir_to_string(&self) -> String {
+        self.module.print_to_string().to_string()
+    }
+
+    /// Returns the function currently being generated.
+    fn current_function(&mut self, span : &Span) -> Option<FunctionValue<'ctx>> {
+        if let Some(function) = self.builder.get_insert_block().and_then(|block| block.get_parent()) {
+            Some(function)
+        } else {
+            self.report(CompilerError::bug()
+                    .reason("instruction outside of a function")
+                    .span(span))
+        }
+    }
+
+    /// Allocates stack memory for a value of this type in the entry block of the current
+    /// function, so that the allocation is not repeated.
+    fn build_entry_alloca(&mut self, ty : BasicTypeEnum<'ctx>, span : &Span) -> Option<PointerValue<'ctx>> {
+        let entry = if let Some(entry) = self.current_function(span)?.get_first_basic_block() {
+            entry
+        } else {
+            return self.report(CompilerError::bug()
+                    .reason("function without an entry block")
+                    .span(span));
+        };
+        let builder = self.context.create_builder();
+        if let Some(inst) = entry.get_first_instruction() {
+            builder.position_before(&inst);
+        } else {
+            builder.position_at_end(entry);
+        }
+        Some(builder.build_alloca(ty, ""))
+    }
+
+    /// Converts a value of type `from` into the type `to`, whose LLVM type is `ty`. Integers are
+    /// sign-extended if `from` is signed, and zero-extended otherwise. Floating point numbers
+    /// saturate when converted into integers, and NaN is converted into zero.
+    fn build_cast(&mut self, value : BasicValueEnum<'ctx>, from : &ir::TypeKind, to : &ir::TypeKind,
+            ty : BasicTypeEnum<'ctx>, span : &Span) -> Option<BasicValueEnum<'ctx>> {
+        let b = &self.builder;
+        let signed = matches!(from, ir::TypeKind::Int(_));
+        let value = match (from, to) {
+            _ if from == to => value,
+            (ir::TypeKind::Float(_), ir::TypeKind::Float(_)) => {
+                b.build_float_cast(value.into_float_value(), ty.into_float_type(), "").as_basic_value_enum()
+            },
+            (ir::TypeKind::Float(_), _) => {
+                let name = if let ir::TypeKind::Int(_) = to { "llvm.fptosi.sat" } else { "llvm.fptoui.sat" };
+                return self.build_intrinsic_call(name, &[ty, value.get_type()], &[value.into()], span);
+            },
+            (_, ir::TypeKind::Float(_)) if signed => {
+                b.build_signed_int_to_float(value.into_int_value(), ty.into_float_type(), "").as_basic_value_enum()
+            },
+            (_, ir::TypeKind::Float(_)) => {
+                b.build_unsigned_int_to_float(value.into_int_value(), ty.into_float_type(), "").as_basic_value_enum()
+            },
+            _ => self.build_int_resize(value.into_int_value(), ty.into_int_type(), signed).as_basic_value_enum()
+        };
+        Some(value)
+    }
+
+    /// Truncates or extends an integer value so that it fits this integer type.
+    fn build_int_resize(&self, value : IntValue<'ctx>, ty : IntType<'ctx>, signed : bool) -> IntValue<'ctx> {
+        let from = value.get_type().get_bit_width();
+        let to = ty.get_bit_width();
+        if from > to {
+            self.builder.build_int_truncate(value, ty, "")
+        } else if from == to {
+            value
+        } else if signed {
+            self.builder.build_int_s_extend(value, ty, "")
+        } else {
+            self.builder.build_int_z_extend(value, ty, "")
+        }
+    }
+
+    /// Returns the declaration of the LLVM intrinsic with this name, overloaded for these types.
+    fn get_intrinsic(&mut self, name : &str, types : &[BasicTypeEnum<'ctx>], span : &Span) -> Option<FunctionValue<'ctx>> {
+        let function = Intrinsic::find(name).and_then(|intrinsic| intrinsic.get_declaration(&self.module, types));
+        if let Some(function) = function {
+            Some(function)
+        } else {
+            self.report(CompilerError::bug()
+                    .reason(format!("unknown intrinsic `{}`", name))
+                    .span(span))
+        }
+    }
+
+    /// Builds a call to the LLVM intrinsic with this name, overloaded for these types, which
+    /// returns a value.
+    fn build_intrinsic_call(&mut self, name : &str, types : &[BasicTypeEnum<'ctx>],
+            args : &[BasicMetadataValueEnum<'ctx>], span : &Span) -> Option<BasicValueEnum<'ctx>> {
+        let intrinsic = self.get_intrinsic(name, types, span)?;
+        if let Some(value) = self.builder.build_call(intrinsic, args, "").try_as_basic_value().left() {
+            Some(value)
+        } else {
+            self.report(CompilerError::bug()
+                    .reason(format!("intrinsic `{}` does not return a value", name))
+                    .span(span))
+        }
+    }
+
+    /// Builds a branch which aborts the program if this condition is true.
+    fn build_trap_if(&mut self, condition : IntValue<'ctx>, span : &Span) -> Option<()> {
+        let function = self.current_function(span)?;
+        let trap_block = self.context.append_basic_block(function, "trap");
+        let next_block = self.context.append_basic_block(function, "next");
+        self.builder.build_conditional_branch(condition, trap_block, next_block);
+        self.builder.position_at_end(trap_block);
+        let trap = self.get_intrinsic("llvm.trap", &[], span)?;
+        self.builder.build_call(trap, &[], "");
+        self.builder.build_unreachable();
+        self.builder.position_at_end(next_block);
+        Some(())
+    }
+
+    /// Builds a call to an arithmetic intrinsic which reports overflow, such as
+    /// `llvm.sadd.with.overflow`, and aborts the program if the result overflows.
+    fn build_checked(&mut self, name : &str, x : IntValue<'ctx>, y : IntValue<'ctx>, span : &Span) -> Option<IntValue<'ctx>> {
+        let result = match self.build_intrinsic_call(name, &[x.get_type().into()], &[x.into(), y.into()], span)? {
+            BasicValueEnum::StructValue(result) => result,
+            _ => return self.report(CompilerError::bug()
+                    .reason(format!("invalid result of intrinsic `{}`", name))
+                    .span(span))
+        };
+        let value = self.builder.build_extract_value(result, 0, "").map(BasicValueEnum::into_int_value);
+        let overflow = self.builder.build_extract_value(result, 1, "").map(BasicValueEnum::into_int_value);
+        if let (Some(value), Some(overflow)) = (value, overflow) {
+            self.build_trap_if(overflow, span)?;
+            Some(value)
+        } else {
+            self.report(CompilerError::bug()
+                    .reason(format!("invalid result of intrinsic `{}`", name))
+                    .span(span))
+        }
+    }
+
+    /// Aborts the program if `x` cannot be divided by `y`, either because `y` is zero or
+    /// because the quotient overflows.
+    fn build_division_check(&mut self, x : IntValue<'ctx>, y : IntValue<'ctx>, signed : bool, span : &Span) -> Option<()> {
+        let ty = x.get_type();
+        let b = &self.builder;
+        let mut invalid = b.build_int_compare(IntPredicate::EQ, y, ty.const_zero(), "");
+        if signed {
+            let min = ty.const_int(1 << (ty.get_bit_width() - 1), false);
+            let overflow = b.build_and(
+                    b.build_int_compare(IntPredicate::EQ, x, min, ""),
+                    b.build_int_compare(IntPredicate::EQ, y, ty.const_all_ones(), ""), "");
+            invalid = b.build_or(invalid, overflow, "");
+        }
+        self.build_trap_if(invalid, span)
+    }
+
+    /// Aborts the program if `y` is not a valid number of bits to shift `x` by.
+    fn build_shift_check(&mut self, y : IntValue<'ctx>, span : &Span) -> Option<()> {
+        let ty = y.get_type();
+        // negative amounts are also rejected, since they are large when compared as unsigned
+        let bits = ty.const_int(ty.get_bit_width() as u64, false);
+        let invalid = self.builder.build_int_compare(IntPredicate::UGE, y, bits, "");
+        self.build_trap_if(invalid, span)
+    }
+
+    fn visit_llvm_type(&mut self, ty : &ir::InstType) -> Option<BasicTypeEnum<'ctx>> {
+        match &ty.kind {
+            ir::TypeKind::Bool => Some(self.context.bool_type().into()),
+            ir::TypeKind::Int(n) | ir::TypeKind::UInt(n) => {
+                Some(self.context.custom_width_int_type(*n as u32).into())
+            },
+            ir::TypeKind::Float(32) => Some(self.context.f32_type().into()),
+            ir::TypeKind::Float(_) => Some(self.context.f64_type().into()),
+            ir::TypeKind::Char => Some(self.context.i32_type().into()),
+            ir::TypeKind::Struct { id, .. } => if let Some(struct_type) = self.structs.get(id) {
+                Some(struct_type.as_basic_type_enum())
+            } else {
+                self.report(CompilerError::unreachable("undeclared struct").span(&ty.span))
+            },
+            ir::TypeKind::Pointer(inner) => {
+                let inner = ir::InstType::new(ty.span.clone(), inner.as_ref().clone());
+                let inner = self.visit_llvm_type(&inner)?;
+                Some(inner.ptr_type(AddressSpace::default()).into())
+            },
+            ir::TypeKind::Empty | ir::TypeKind::Void => Some(self.context.struct_type(&[], false).into()),
+            ir::TypeKind::Str => {
+                let ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+                Some(self.context.struct_type(&[ptr_type.into(), self.context.i64_type().into()], false).into())
+            },
+            ir::TypeKind::Tuple(_) => self.report(CompilerError::unimplemented("tuple types").span(&ty.span)),
+            ir::TypeKind::Array { .. } => self.report(CompilerError::unimplemented("array types").span(&ty.span)),
+            ir::TypeKind::Function { .. } => self.report(CompilerError::unimplemented("function types").span(&ty.span)),
+            ir::TypeKind::Infer | ir::TypeKind::Unknown { .. } | ir::TypeKind::Variable { .. } => self.report(
+                    CompilerError::unreachable("untyped").span(&ty.span))?
+        }
+    }
+
+    fn visit_llvm_inst(&mut self, inst : ir::Inst) -> Option<BasicValueEnum<'ctx>> {
+        let span = inst.span;
+        let ty = self.visit_llvm_type(&inst.datatype)?;
+        match inst.kind {
+            ir::InstKind::Variable => self.report(
+                    CompilerError::unreachable("unresolved variable").span(&span)),
+            ir::InstKind::Local { id } => if let Some(ptr) = self.variables.get(&id) {
+                Some(self.builder.build_load(*ptr, ""))
+            } else if let Some(value) = self.locals.get(&id) {
+                Some(*value)
+            } else {
+                self.report(CompilerError::unreachable("unbound local variable").span(&span))
+            },
+            ir::InstKind::Block { stmts, value } => {
+                for stmt in stmts {
+                    self.visit_llvm_inst(stmt)?;
+                }
+                if let Some(value) = value {
+                    self.visit_llvm_inst(*value)
+                } else {
+                    Some(ty.into_struct_type().get_undef().into())
+                }
+            },
+            ir::InstKind::If { condition, on_true, on_false } => {
+                let condition = self.visit_llvm_inst(*condition)?.into_int_value();
+                let function = self.current_function(&span)?;
+                let then_block = self.context.append_basic_block(function, "then");
+                let else_block = self.context.append_basic_block(function, "else");
+                let merge_block = self.context.append_basic_block(function, "merge");
+                self.builder.build_conditional_branch(condition, then_block, else_block);
+                let mut incoming = Vec::new();
+                for (block, branch) in [(then_block, on_true), (else_block, on_false)] {
+                    self.builder.position_at_end(block);
+                    let diverges = branch.datatype.kind == ir::TypeKind::Void;
+                    let value = self.visit_llvm_inst(*branch)?;
+                    if diverges {
+                        self.builder.build_unreachable();
+                        continue;
+                    }
+                    if let Some(end) = self.builder.get_insert_block() {
+                        incoming.push((value, end));
+                    }
+                    self.builder.build_unconditional_branch(merge_block);
+                }
+                self.builder.position_at_end(merge_block);
+                if incoming.is_empty() {
+                    return Some(ty.into_struct_type().get_undef().into());
+                }
+                let phi = self.builder.build_phi(ty, "");
+                for (value, block) in &incoming {
+                    phi.add_incoming(&[(value as &dyn BasicValue, *block)]);
+                }
+                Some(phi.as_basic_value())
+            },
+            ir::InstKind::Assign { target, value } => {
+                let value = self.visit_llvm_inst(*value)?;
+                let ptr = self.visit_llvm_place(*target)?;
+                self.builder.build_store(ptr, value);
+                Some(ty.into_struct_type().get_undef().into())
+            },
+            ir::InstKind::Loop { body } => {
+                let function = self.current_function(&span)?;
+                let body_block = self.context.append_basic_block(function, "loop");
+                let exit_block = self.context.append_basic_block(function, "exit");
+                self.builder.build_unconditional_branch(body_block);
+                self.builder.position_at_end(body_block);
+                self.loops.push((body_block, exit_block));
+                let result = self.visit_llvm_inst(*body);
+                self.loops.pop();
+                result?;
+                self.builder.build_unconditional_branch(body_block);
+                self.builder.position_at_end(exit_block);
+                Some(ty.into_struct_type().get_undef().into())
+            },
+            ir::InstKind::Break | ir::InstKind::Continue => {
+                let (body_block, exit_block) = if let Some(blocks) = self.loops.last() {
+                    *blocks
+                } else {
+                    return self.report(CompilerError::unreachable("jump outside of a loop").span(&span));
+                };
+                let target = if let ir::InstKind::Break = inst.kind { exit_block } else { body_block };
+                self.builder.build_unconditional_branch(target);
+                // any instructions after the jump are unreachable
+                let function = self.current_function(&span)?;
+                let dead_block = self.context.append_basic_block(function, "dead");
+                self.builder.position_at_end(dead_block);
+                Some(ty.into_struct_type().get_undef().into())
+            },
+            ir::InstKind::Let { id, mutable, value, body, .. } => {
+                let value = self.visit_llvm_inst(*value)?;
+                if mutable {
+                    let ptr = self.build_entry_alloca(value.get_type(), &span)?;
+                    self.builder.build_store(ptr, value);
+                    self.variables.insert(id, ptr);
+                } else {
+                    self.locals.insert(id, value);
+                }
+                self.visit_llvm_inst(*body)
+            },
+            ir::InstKind::Integral { value } => {
+                Some(ty.into_int_type().const_int(value as u64, false).into())
+            },
+            ir::InstKind::Boolean { value } => {
+                Some(self.context.bool_type().const_int(value as u64, false).into())
+            },
+            ir::InstKind::Float { value } => Some(ty.into_float_type().const_float(value).into()),
+            ir::InstKind::Char { value } => {
+                Some(self.context.i32_type().const_int(value as u64, false).into())
+            },
+            ir::InstKind::String { value } => Some(self.build_string(&value, ty.into_struct_type())),
+            ir::InstKind::Struct { fields } => {
+                let mut value = ty.into_struct_type().get_undef();
+                for (_, index, field) in fields {
+                    let field = self.visit_llvm_inst(field)?;
+                    value = match self.builder.build_insert_value(value, field, index as u32, "") {
+                        Some(value) => value.into_struct_value(),
+                        None => return self.report(CompilerError::bug()
+                                .reason("invalid struct field")
+                                .span(&span))
+                    };
+                }
+                Some(value.into())
+            },
+            ir::InstKind::Field { value, index, .. } => {
+                let value = self.visit_llvm_inst(*value)?.into_struct_value();
+                if let Some(field) = self.builder.build_extract_value(value, index as u32, "") {
+                    Some(field)
+                } else {
+                    self.report(CompilerError::bug()
+                            .reason("invalid struct field")
+                            .span(&span))
+                }
+            },
+            ir::InstKind::Reference { value } => {
+                if let ir::InstKind::Local { id } = value.kind {
+                    if let Some(ptr) = self.variables.get(&id) {
+                        return Some((*ptr).into());
+                    }
+                }
+                let value = self.visit_llvm_inst(*value)?;
+                let ptr = self.build_entry_alloca(value.get_type(), &span)?;
+                self.builder.build_store(ptr, value);
+                Some(ptr.into())
+            },
+            ir::InstKind::Dereference { value } => {
+                let ptr = self.visit_llvm_inst(*value)?.into_pointer_value();
+                Some(self.builder.build_load(ptr, ""))
+            },
+            ir::InstKind::Cast { value, .. } => {
+                let from = value.datatype.kind.clone();
+                let value = self.visit_llvm_inst(*value)?;
+                self.build_cast(value, &from, &inst.datatype.kind, ty, &span)
+            },
+            ir::InstKind::Global { id } => self.build_item_call(id, Vec::new(), &span),
+            ir::InstKind::Associated { .. } | ir::InstKind::Method { .. } => self.report(
+                    CompilerError::unreachable("unresolved associated item").span(&span)),
+            ir::InstKind::Builtin { .. } => self.report(
+                    CompilerError::unimplemented("first-class operators").span(&span)),
+            ir::InstKind::FunctionApp { callsite, args } => {
+                let operand = args.first().map_or(ir::TypeKind::Empty, |arg| arg.datatype.kind.clone());
+                let mut values = Vec::new();
+                for arg in args {
+                    values.push(self.visit_llvm_inst(arg)?);
+                }
+                self.visit_llvm_app(*callsite, values, &operand, (ty, &inst.datatype.kind), &span)
+            }
+        }
+    }
+
+    /// Returns a pointer to the memory an assignment target refers to.
+    fn visit_llvm_place(&mut self, inst : ir::Inst) -> Option<PointerValue<'ctx>> {
+        let span = inst.span;
+        match inst.kind {
+            ir::InstKind::Local { id } => if let Some(ptr) = self.variables.get(&id) {
+                Some(*ptr)
+            } else {
+                self.report(CompilerError::unreachable("assignment to an immutable variable").span(&span))
+            },
+            ir::InstKind::Field { value, index, .. } => {
+                let ptr = self.visit_llvm_place(*value)?;
+                if let Ok(field) = self.builder.build_struct_gep(ptr, index as u32, "") {
+                    Some(field)
+                } else {
+                    self.report(CompilerError::bug()
+                            .reason("invalid struct field")
+                            .span(&span))
+                }
+            },
+            ir::InstKind::Dereference { value } => Some(self.visit_llvm_inst(*value)?.into_pointer_value()),
+            _ => self.report(CompilerError::unreachable("invalid assignment target").span(&span))
+        }
+    }
+
+    /// Builds the application of a function to these arguments, where `operand` is the type
+    /// of the first argument and `result` is the type of the application.
+    fn visit_llvm_app(&mut self, callsite : ir::Inst, args : Vec<BasicValueEnum<'ctx>>, operand : &ir::TypeKind,
+            result : (BasicTypeEnum<'ctx>, &ir::TypeKind), span : &Span) -> Option<BasicValueEnum<'ctx>> {
+        use ir::BuiltinOp as Op;
+        let signed = matches!(operand, ir::TypeKind::Int(_));
+        let op = match callsite.kind {
+            ir::InstKind::Builtin { op } => op,
+            ir::InstKind::Global { id } => return self.build_item_call(id, args, span),
+            _ => return self.report(CompilerError::unimplemented("first-class functions").span(&callsite.span))
+        };
+        let b = &self.builder;
+        match (op, args.as_slice()) {
+            (Op::Print, [value]) => return self.build_print((*value).into_struct_value(), span),
+            (Op::ToFloat, [value]) | (Op::ToInt, [value]) => {
+                return self.build_cast(*value, operand, result.1, result.0, span);
+            },
+            _ => ()
+        }
+        if let ir::TypeKind::Float(_) = operand {
+            let args = args.into_iter()
+                    .map(BasicValueEnum::into_float_value)
+                    .collect::<Vec<_>>();
+            let value = match (op, args.as_slice()) {
+                (Op::Add, [x, y]) => b.build_float_add(*x, *y, "").as_basic_value_enum(),
+                (Op::Sub, [x, y]) => b.build_float_sub(*x, *y, "").as_basic_value_enum(),
+                (Op::Mul, [x, y]) => b.build_float_mul(*x, *y, "").as_basic_value_enum(),
+                (Op::Div, [x, y]) => b.build_float_div(*x, *y, "").as_basic_value_enum(),
+                (Op::Neg, [x]) => b.build_float_neg(*x, "").as_basic_value_enum(),
+                (op, [x, y]) if op.is_comparison() => {
+                    let predicate = match op {
+                        Op::Eq => FloatPredicate::OEQ,
+                        Op::Ne => FloatPredicate::UNE,
+                        Op::Lt => FloatPredicate::OLT,
+                        Op::Le => FloatPredicate::OLE,
+                        Op::Gt => FloatPredicate::OGT,
+                        _ => FloatPredicate::OGE
+                    };
+                    b.build_float_compare(predicate, *x, *y, "").as_basic_value_enum()
+                },
+                _ => return self.report(CompilerError::unreachable(
+                        format!("application of `{}` to {} floating point argument(s)", op, args.len())).span(span))
+            };
+            return Some(value);
+        }
+        let args = args.into_iter()
+                .map(BasicValueEnum::into_int_value)
+                .collect::<Vec<_>>();
+        let (add, sub, mul) = if signed {
+            ("llvm.sadd.with.overflow", "llvm.ssub.with.overflow", "llvm.smul.with.overflow")
+        } else {
+            ("llvm.uadd.with.overflow", "llvm.usub.with.overflow", "llvm.umul.with.overflow")
+        };
+        let value = match (op, args.as_slice()) {
+            (Op::Add, [x, y]) => self.build_checked(add, *x, *y, span)?,
+            (Op::Sub, [x, y]) => self.build_checked(sub, *x, *y, span)?,
+            (Op::Mul, [x, y]) => self.build_checked(mul, *x, *y, span)?,
+            (Op::Neg, [x]) => self.build_checked(sub, x.get_type().const_zero(), *x, span)?,
+            (Op::Div, [x, y]) | (Op::Rem, [x, y]) => {
+                self.build_division_check(*x, *y, signed, span)?;
+                let b = &self.builder;
+                match op {
+                    Op::Div if signed => b.build_int_signed_div(*x, *y, ""),
+                    Op::Div => b.build_int_unsigned_div(*x, *y, ""),
+                    _ if signed => b.build_int_signed_rem(*x, *y, ""),
+                    _ => b.build_int_unsigned_rem(*x, *y, "")
+                }
+            },
+            (Op::Shl, [x, y]) | (Op::Shr, [x, y]) => {
+                self.build_shift_check(*y, span)?;
+                let b = &self.builder;
+                if op == Op::Shl {
+                    b.build_left_shift(*x, *y, "")
+                } else {
+                    b.build_right_shift(*x, *y, signed, "")
+                }
+            },
+            (Op::BitAnd, [x, y]) => b.build_and(*x, *y, ""),
+            (Op::BitOr, [x, y]) => b.build_or(*x, *y, ""),
+            (op, [x, y]) if op.is_comparison() => {
+                let predicate = match op {
+                    Op::Eq => IntPredicate::EQ,
+                    Op::Ne => IntPredicate::NE,
+                    Op::Lt if signed => IntPredicate::SLT,
+                    Op::Lt => IntPredicate::ULT,
+                    Op::Le if signed => IntPredicate::SLE,
+                    Op::Le => IntPredicate::ULE,
+                    Op::Gt if signed => IntPredicate::SGT,
+                    Op::Gt => IntPredicate::UGT,
+                    Op::Ge if signed => IntPredicate::SGE,
+                    _ => IntPredicate::UGE
+                };
+                b.build_int_compare(predicate, *x, *y, "")
+            },
+            (Op::BitNot, [x]) => b.build_not(*x, ""),
+            _ => return self.report(CompilerError::unreachable(
+                    format!("application of `{}` to {} argument(s)", op, args.len())).span(span))
+        };
+        Some(value.as_basic_value_enum())
+    }
+}
+
+/// Compiles the LLVM-IR for this module and returns its textual representation.
+pub fn compile_ir(module : ir::Module, src : &str, issues : &mut IssueTracker, level : u8) -> Option<String> {
+    let context = Context::create();
+    let mut codegen = Codegen::new(&context, "mod", src, issues);
+    codegen.generate_main(module)?;
+    codegen.verify()?;
+    codegen.optimize(level);
+    Some(codegen.print_ir_to_string())
 }
 
-/// Compiles the LLVM-IR for this instruction.
-pub fn compile_ir(inst : ir::Inst, src : &str, issues : &mut IssueTracker) -> Option<()> {
+/// Compiles this module for the host machine and writes the result to a file of this kind.
+pub fn compile_to_file(module : ir::Module, src : &str, issues : &mut IssueTracker,
+        kind : FileKind, path : &str, level : u8) -> Option<()> {
     let context = Context::create();
-    let codegen = Codegen::new(&context, "mod", src, issues);
-    codegen.generate_main();
-    codegen.print_ir_to_stderr();
-    Some(())
+    let mut codegen = Codegen::new(&context, "mod", src, issues);
+    codegen.generate_main(module)?;
+    codegen.verify()?;
+    codegen.optimize(level);
+    codegen.write_to_file(kind, path, level)
+}
+
+/// Compiles this module using the JIT, runs its `main` function, and returns the textual
+/// representation of its value.
+pub fn run_jit(module : ir::Module, src : &str, issues : &mut IssueTracker, level : u8) -> Option<String> {
+    let signed = module.find_item("main", src)
+            .map(|id| returns_signed(&module.items[id]))
+            .unwrap_or(false);
+    let context = Context::create();
+    let mut codegen = Codegen::new(&context, "mod", src, issues);
+    codegen.generate_module(module, JIT_ENTRY, context.i64_type())?;
+    codegen.verify()?;
+    codegen.optimize(level);
+    let value = codegen.run_jit(level)?;
+    if signed {
+        Some((value as i64).to_string())
+    } else {
+        Some(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libcosyc_ir::{ generate_ir, eval::evaluate };
+    use libcosyc_parse::build_ast;
+
+    /// Parses and type checks this source.
+    fn compile(src : &str, issues : &mut IssueTracker) -> ir::Module {
+        build_ast(src, issues)
+                .and_then(|ast| generate_ir(ast, src, issues))
+                .expect("the program should be valid")
+    }
+
+    /// Asserts that running the `main` function of this source using the JIT gives the same
+    /// value as the interpreter.
+    fn assert_same_as_eval(src : &str) {
+        let mut issues = IssueTracker::default();
+        let expected = evaluate(&compile(src, &mut issues), src, &mut issues).map(|value| value.to_string());
+        assert!(expected.is_some(), "{}", src);
+        let actual = run_jit(compile(src, &mut issues), src, &mut issues, 0);
+        assert_eq!(actual, expected, "{}", src);
+    }
+
+    #[test]
+    fn checked_arithmetic_matches_the_interpreter() {
+        assert_same_as_eval("fn main() : int8 { let x : int8 = 126; x + 1 }");
+        assert_same_as_eval("fn main() : uint32 { let x : uint32 = 4000000000; x - 1 }");
+        assert_same_as_eval("fn main() : int64 { let x : int64 = -9223372036854775807; x - 1 }");
+        assert_same_as_eval("fn main() : int32 { let x = -7; x / 2 * 10 + x % 2 }");
+        assert_same_as_eval("fn main() : int8 { let x : int8 = -128; x / 1 + -(x + 1) }");
+        assert_same_as_eval("fn main() : uint8 { let x : uint8 = 255; x / 2 + x % 7 }");
+    }
+
+    #[test]
+    fn shifts_match_the_interpreter() {
+        assert_same_as_eval("fn main() : int8 { let x : int8 = 100; x << 1 }");
+        assert_same_as_eval("fn main() : uint8 { let x : uint8 = 200; x << 1 }");
+        assert_same_as_eval("fn main() : int32 { let x = -64; x >> 3 }");
+        assert_same_as_eval("fn main() : uint64 { let x : uint64 = 1; x << 63 >> 1 }");
+    }
+
+    #[test]
+    fn float_to_int_casts_match_the_interpreter() {
+        assert_same_as_eval("fn main() : int32 { let z = 0.0; (z / z) as int32 }");
+        assert_same_as_eval("fn main() : int64 { let z = 0.0; (-1.0 / z) as int64 }");
+        assert_same_as_eval("fn main() : uint64 { let x = 1e30; x as uint64 }");
+        assert_same_as_eval("fn main() : uint8 { let x = -5.5; x as uint8 }");
+        assert_same_as_eval("fn main() : int32 { let x : float32 = 3e9; x as int32 }");
+        assert_same_as_eval("fn main() : uint8 { let x = 1e9; let y : uint8 = to_int(x); y }");
+    }
 }
@@ -0,0 +1,351 @@
+use libcosyc_diagnostic::source::Span;
+use libcosyc_scan::{ Lexer, token::TokenKind };
+use libcosyc_parse::syntax as ast;
+use libcosyc_ir::ir;
+use std::fmt::Write;
+
+const INDENTATION : &str = "  ";
+
+/// Writes a single line of a tree dump, including the span and its source.
+fn write_node(out : &mut String, depth : usize, label : &str, node : String, span : &Span, src : &str) {
+    let _ = write!(out, "{}", INDENTATION.repeat(depth));
+    if !label.is_empty() {
+        let _ = write!(out, "{}: ", label);
+    }
+    let _ = writeln!(out, "{} {} {:?}", node, span, span.render(src));
+}
+
+/// Dumps the token stream of this source code, including the span of each token.
+/// Whitespace tokens are omitted.
+pub fn tokens(src : &str) -> String {
+    let mut out = String::new();
+    let mut lexer = Lexer::from(src);
+    loop {
+        let token = lexer.generate_token();
+        if matches!(token, TokenKind::Whitestuff) {
+            continue;
+        }
+        let span = lexer.span();
+        let _ = writeln!(out, "{} {:?} {:?}", span, token, span.render(src));
+        if matches!(token, TokenKind::EoF) {
+            break out;
+        }
+    }
+}
+
+/// Dumps the tree of each item in this AST module.
+pub fn ast(module : &ast::Module, src : &str) -> String {
+    let mut out = String::new();
+    for item in &module.items {
+        visit_item(&mut out, 0, item, src);
+    }
+    out
+}
+
+fn visit_item(out : &mut String, depth : usize, item : &ast::Item, src : &str) {
+    let name = item.name.render(src);
+    match &item.kind {
+        ast::ItemKind::Function { params, return_type, body } => {
+            write_node(out, depth, "", format!("Function({})", name), &item.span, src);
+            for param in params {
+                visit_type(out, depth + 1, &format!("param {}", param.name.render(src)), &param.datatype, src);
+            }
+            visit_type(out, depth + 1, "return", return_type, src);
+            visit_term(out, depth + 1, "body", body, src);
+        },
+        ast::ItemKind::Definition { datatype, value } => {
+            write_node(out, depth, "", format!("Definition({})", name), &item.span, src);
+            visit_type(out, depth + 1, "type", datatype, src);
+            visit_term(out, depth + 1, "value", value, src);
+        },
+        ast::ItemKind::Data { datatype } => {
+            write_node(out, depth, "", format!("Data({})", name), &item.span, src);
+            visit_type(out, depth + 1, "type", datatype, src);
+        },
+        ast::ItemKind::Impl { items } => {
+            write_node(out, depth, "", format!("Impl({})", name), &item.span, src);
+            for item in items {
+                visit_item(out, depth + 1, item, src);
+            }
+        }
+    }
+}
+
+fn visit_type(out : &mut String, depth : usize, label : &str, ty : &ast::Type, src : &str) {
+    let span = &ty.span;
+    match &ty.kind {
+        ast::TypeKind::Named => write_node(out, depth, label, "NamedType".to_string(), span, src),
+        ast::TypeKind::Pointer { datatype } => {
+            write_node(out, depth, label, "PointerType".to_string(), span, src);
+            visit_type(out, depth + 1, "type", datatype, src);
+        },
+        ast::TypeKind::Function { params, return_type } => {
+            write_node(out, depth, label, "FunctionType".to_string(), span, src);
+            for param in params {
+                visit_type(out, depth + 1, "param", param, src);
+            }
+            visit_type(out, depth + 1, "return", return_type, src);
+        },
+        ast::TypeKind::Tuple { elements } => {
+            write_node(out, depth, label, "TupleType".to_string(), span, src);
+            for element in elements {
+                visit_type(out, depth + 1, "element", element, src);
+            }
+        },
+        ast::TypeKind::Array { element, length } => {
+            write_node(out, depth, label, "ArrayType".to_string(), span, src);
+            visit_type(out, depth + 1, "element", element, src);
+            visit_term(out, depth + 1, "length", length, src);
+        },
+        ast::TypeKind::Struct { fields } => {
+            write_node(out, depth, label, "StructType".to_string(), span, src);
+            for field in fields {
+                visit_type(out, depth + 1, &format!("field {}", field.name.render(src)), &field.datatype, src);
+            }
+        }
+    }
+}
+
+fn visit_term(out : &mut String, depth : usize, label : &str, term : &ast::Term, src : &str) {
+    let span = &term.span;
+    match &term.kind {
+        ast::TermKind::Variable => write_node(out, depth, label, "Variable".to_string(), span, src),
+        ast::TermKind::Integral { radix } => {
+            write_node(out, depth, label, format!("Integral(radix={})", radix), span, src);
+        },
+        ast::TermKind::Char => {
+            write_node(out, depth, label, "Char".to_string(), span, src);
+        },
+        ast::TermKind::String => {
+            write_node(out, depth, label, "String".to_string(), span, src);
+        },
+        ast::TermKind::Float => {
+            write_node(out, depth, label, "Float".to_string(), span, src);
+        },
+        ast::TermKind::Boolean { value } => {
+            write_node(out, depth, label, format!("Boolean({})", value), span, src);
+        },
+        ast::TermKind::TypeAnno { value, datatype } => {
+            write_node(out, depth, label, "TypeAnno".to_string(), span, src);
+            visit_term(out, depth + 1, "value", value, src);
+            visit_type(out, depth + 1, "type", datatype, src);
+        },
+        ast::TermKind::BinaryOp { op, left, right } => {
+            write_node(out, depth, label, format!("BinaryOp({})", op.render(src)), span, src);
+            visit_term(out, depth + 1, "left", left, src);
+            visit_term(out, depth + 1, "right", right, src);
+        },
+        ast::TermKind::UnaryOp { op, value } => {
+            write_node(out, depth, label, format!("UnaryOp({})", op.render(src)), span, src);
+            visit_term(out, depth + 1, "value", value, src);
+        },
+        ast::TermKind::Call { callsite, args } => {
+            write_node(out, depth, label, "Call".to_string(), span, src);
+            visit_term(out, depth + 1, "callsite", callsite, src);
+            for arg in args {
+                visit_term(out, depth + 1, "arg", arg, src);
+            }
+        },
+        ast::TermKind::StructLit { fields } => {
+            write_node(out, depth, label, "StructLit".to_string(), span, src);
+            for field in fields {
+                visit_term(out, depth + 1, &format!("field {}", field.name.render(src)), &field.term, src);
+            }
+        },
+        ast::TermKind::Associated { datatype, name } => {
+            write_node(out, depth, label, format!("Associated({})", name.render(src)), span, src);
+            visit_type(out, depth + 1, "type", datatype, src);
+        },
+        ast::TermKind::Field { value, field } => {
+            write_node(out, depth, label, format!("Field({})", field.render(src)), span, src);
+            visit_term(out, depth + 1, "value", value, src);
+        },
+        ast::TermKind::Cast { value, datatype } => {
+            write_node(out, depth, label, "Cast".to_string(), span, src);
+            visit_term(out, depth + 1, "value", value, src);
+            visit_type(out, depth + 1, "type", datatype, src);
+        },
+        ast::TermKind::Reference { value } => {
+            write_node(out, depth, label, "Reference".to_string(), span, src);
+            visit_term(out, depth + 1, "value", value, src);
+        },
+        ast::TermKind::Dereference { value } => {
+            write_node(out, depth, label, "Dereference".to_string(), span, src);
+            visit_term(out, depth + 1, "value", value, src);
+        },
+        ast::TermKind::Block { stmts, value } => {
+            write_node(out, depth, label, "Block".to_string(), span, src);
+            for stmt in stmts {
+                visit_term(out, depth + 1, "stmt", stmt, src);
+            }
+            if let Some(value) = value {
+                visit_term(out, depth + 1, "value", value, src);
+            }
+        },
+        ast::TermKind::If { condition, on_true, on_false } => {
+            write_node(out, depth, label, "If".to_string(), span, src);
+            visit_term(out, depth + 1, "condition", condition, src);
+            visit_term(out, depth + 1, "then", on_true, src);
+            if let Some(on_false) = on_false {
+                visit_term(out, depth + 1, "else", on_false, src);
+            }
+        },
+        ast::TermKind::Assign { target, value } => {
+            write_node(out, depth, label, "Assign".to_string(), span, src);
+            visit_term(out, depth + 1, "target", target, src);
+            visit_term(out, depth + 1, "value", value, src);
+        },
+        ast::TermKind::While { condition, body } => {
+            write_node(out, depth, label, "While".to_string(), span, src);
+            visit_term(out, depth + 1, "condition", condition, src);
+            visit_term(out, depth + 1, "body", body, src);
+        },
+        ast::TermKind::Loop { body } => {
+            write_node(out, depth, label, "Loop".to_string(), span, src);
+            visit_term(out, depth + 1, "body", body, src);
+        },
+        ast::TermKind::Break => write_node(out, depth, label, "Break".to_string(), span, src),
+        ast::TermKind::Continue => write_node(out, depth, label, "Continue".to_string(), span, src),
+        ast::TermKind::Let { name, mutable, value, body } => {
+            let node = if *mutable { "LetMut" } else { "Let" };
+            write_node(out, depth, label, format!("{}({})", node, name.render(src)), span, src);
+            visit_term(out, depth + 1, "value", value, src);
+            visit_term(out, depth + 1, "body", body, src);
+        }
+    }
+}
+
+/// Dumps the tree of each item in this typed IR module.
+pub fn ir(module : &ir::Module, src : &str) -> String {
+    let mut out = String::new();
+    for (id, item) in module.items.iter().enumerate() {
+        let name = item.qualified_name(src);
+        match &item.kind {
+            ir::ItemKind::Function { params, return_type, body } => {
+                write_node(&mut out, 0, "", format!("Function(@{} = {}) : {}", id, name, return_type.kind), &item.span, src);
+                for param in params {
+                    let label = format!("param {} = %{}", param.name.render(src), param.id);
+                    write_node(&mut out, 1, &label, param.datatype.kind.to_string(), &param.datatype.span, src);
+                }
+                visit_inst(&mut out, 1, "body", body, src);
+            },
+            ir::ItemKind::Definition { value } => {
+                write_node(&mut out, 0, "", format!("Definition(@{} = {})", id, name), &item.span, src);
+                visit_inst(&mut out, 1, "value", value, src);
+            },
+            ir::ItemKind::Data { datatype } => {
+                write_node(&mut out, 0, "", format!("Data(@{} = {}) : {}", id, name, datatype.kind), &item.span, src);
+            },
+            ir::ItemKind::Struct { fields } => {
+                write_node(&mut out, 0, "", format!("Struct(@{} = {})", id, name), &item.span, src);
+                for field in fields {
+                    let label = format!("field {}", field.name.render(src));
+                    write_node(&mut out, 1, &label, field.datatype.kind.to_string(), &field.datatype.span, src);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn visit_inst(out : &mut String, depth : usize, label : &str, inst : &ir::Inst, src : &str) {
+    let span = &inst.span;
+    let ty = &inst.datatype.kind;
+    match &inst.kind {
+        ir::InstKind::Variable => {
+            write_node(out, depth, label, format!("Variable : {}", ty), span, src);
+        },
+        ir::InstKind::Local { id } => {
+            write_node(out, depth, label, format!("Local(%{}) : {}", id, ty), span, src);
+        },
+        ir::InstKind::Global { id } => {
+            write_node(out, depth, label, format!("Global(@{}) : {}", id, ty), span, src);
+        },
+        ir::InstKind::Associated { datatype, name } => {
+            write_node(out, depth, label, format!("Associated({}::{}) : {}", datatype.kind, name.render(src), ty), span, src);
+        },
+        ir::InstKind::Method { name } => {
+            write_node(out, depth, label, format!("Method({}) : {}", name.render(src), ty), span, src);
+        },
+        ir::InstKind::Builtin { op } => {
+            write_node(out, depth, label, format!("Builtin({}) : {}", op, ty), span, src);
+        },
+        ir::InstKind::Integral { value } => {
+            write_node(out, depth, label, format!("Integral({}) : {}", value, ty), span, src);
+        },
+        ir::InstKind::Float { value } => {
+            write_node(out, depth, label, format!("Float({:?}) : {}", value, ty), span, src);
+        },
+        ir::InstKind::Boolean { value } => {
+            write_node(out, depth, label, format!("Boolean({}) : {}", value, ty), span, src);
+        },
+        ir::InstKind::Char { value } => {
+            write_node(out, depth, label, format!("Char({:?}) : {}", value, ty), span, src);
+        },
+        ir::InstKind::String { value } => {
+            let value = String::from_utf8_lossy(value);
+            write_node(out, depth, label, format!("String({:?}) : {}", value, ty), span, src);
+        },
+        ir::InstKind::FunctionApp { callsite, args } => {
+            write_node(out, depth, label, format!("FunctionApp : {}", ty), span, src);
+            visit_inst(out, depth + 1, "callsite", callsite, src);
+            for arg in args {
+                visit_inst(out, depth + 1, "arg", arg, src);
+            }
+        },
+        ir::InstKind::Struct { fields } => {
+            write_node(out, depth, label, format!("Struct : {}", ty), span, src);
+            for (name, index, value) in fields {
+                visit_inst(out, depth + 1, &format!("field {}#{}", name.render(src), index), value, src);
+            }
+        },
+        ir::InstKind::Field { value, name, index } => {
+            write_node(out, depth, label, format!("Field({}#{}) : {}", name.render(src), index, ty), span, src);
+            visit_inst(out, depth + 1, "value", value, src);
+        },
+        ir::InstKind::Cast { value, .. } => {
+            write_node(out, depth, label, format!("Cast : {}", ty), span, src);
+            visit_inst(out, depth + 1, "value", value, src);
+        },
+        ir::InstKind::Reference { value } => {
+            write_node(out, depth, label, format!("Reference : {}", ty), span, src);
+            visit_inst(out, depth + 1, "value", value, src);
+        },
+        ir::InstKind::Dereference { value } => {
+            write_node(out, depth, label, format!("Dereference : {}", ty), span, src);
+            visit_inst(out, depth + 1, "value", value, src);
+        },
+        ir::InstKind::Block { stmts, value } => {
+            write_node(out, depth, label, format!("Block : {}", ty), span, src);
+            for stmt in stmts {
+                visit_inst(out, depth + 1, "stmt", stmt, src);
+            }
+            if let Some(value) = value {
+                visit_inst(out, depth + 1, "value", value, src);
+            }
+        },
+        ir::InstKind::If { condition, on_true, on_false } => {
+            write_node(out, depth, label, format!("If : {}", ty), span, src);
+            visit_inst(out, depth + 1, "condition", condition, src);
+            visit_inst(out, depth + 1, "then", on_true, src);
+            visit_inst(out, depth + 1, "else", on_false, src);
+        },
+        ir::InstKind::Assign { target, value } => {
+            write_node(out, depth, label, format!("Assign : {}", ty), span, src);
+            visit_inst(out, depth + 1, "target", target, src);
+            visit_inst(out, depth + 1, "value", value, src);
+        },
+        ir::InstKind::Loop { body } => {
+            write_node(out, depth, label, format!("Loop : {}", ty), span, src);
+            visit_inst(out, depth + 1, "body", body, src);
+        },
+        ir::InstKind::Break => write_node(out, depth, label, format!("Break : {}", ty), span, src),
+        ir::InstKind::Continue => write_node(out, depth, label, format!("Continue : {}", ty), span, src),
+        ir::InstKind::Let { id, name, mutable, value, body } => {
+            let node = if *mutable { "LetMut" } else { "Let" };
+            write_node(out, depth, label, format!("{}({} = %{}) : {}", node, name.render(src), id, ty), span, src);
+            visit_inst(out, depth + 1, "value", value, src);
+            visit_inst(out, depth + 1, "body", body, src);
+        }
+    }
+}
@@ -1,25 +1,256 @@
-use libcosyc_diagnostic::Session;
+pub mod dump;
+
+use libcosyc_diagnostic::{ Session, error::CompilerError };
 use libcosyc_parse as parse;
 use libcosyc_ir as cosyir;
-use libcosyc_codegen::llvm as codegen;
+use libcosyc_codegen::{ c, llvm };
+use std::{ env, fs, path::Path, process::Command, io::{ self, Write } };
+
+/// Represents the available code generation backends.
+#[derive(Default, PartialEq, Eq, Debug, Clone)]
+pub enum Backend {
+    #[default]
+    C,
+    LLVM
+}
+
+impl Backend {
+    /// Attempts to find a backend with this name.
+    pub fn from_name(name : &str) -> Option<Self> {
+        let backend = match name {
+            "c" => Self::C,
+            "llvm" => Self::LLVM,
+            _ => return None
+        };
+        Some(backend)
+    }
+}
+
+/// Represents a stage of the compiler pipeline whose output can be emitted.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Emit {
+    /// The token stream produced by the lexer.
+    Tokens,
+    /// The abstract syntax tree produced by the parser.
+    Ast,
+    /// The typed intermediate representation.
+    Ir,
+    /// The code generated by the C backend.
+    C,
+    /// The code generated by the LLVM backend.
+    LLVM,
+    /// The LLVM bitcode generated by the LLVM backend.
+    Bitcode,
+    /// A native assembly file.
+    Asm,
+    /// A native object file.
+    Obj,
+    /// An executable file.
+    Exe
+}
+
+impl Emit {
+    /// Attempts to find an emit stage with this name.
+    pub fn from_name(name : &str) -> Option<Self> {
+        let emit = match name {
+            "tokens" => Self::Tokens,
+            "ast" => Self::Ast,
+            "ir" => Self::Ir,
+            "c" => Self::C,
+            "llvm-ir" => Self::LLVM,
+            "llvm-bc" => Self::Bitcode,
+            "asm" => Self::Asm,
+            "obj" => Self::Obj,
+            "exe" => Self::Exe,
+            _ => return None
+        };
+        Some(emit)
+    }
+
+    /// Returns whether this stage produces a native file.
+    pub fn is_native(&self) -> bool {
+        matches!(self, Self::Bitcode | Self::Asm | Self::Obj | Self::Exe)
+    }
+}
+
+/// Configures how a program is compiled.
+#[derive(Default, Debug, Clone)]
+pub struct Options {
+    /// The backend used to generate code.
+    pub backend : Backend,
+    /// The stage to stop after and emit. Uses the default output of the backend if `None`.
+    pub emit : Option<Emit>,
+    /// The file to write the output to.
+    pub output : Option<String>,
+    /// The optimisation level, from 0 to 3.
+    pub opt_level : u8
+}
 
 /// Starts a new compiler session using this file path.
 pub fn open(path : &str) -> Session {
     Session::load(path)
 }
 
-/// Compiles this program to the desired level.
-pub fn compile(sess : &mut Session) -> Option<()> {
+/// Compiles this program to the desired level and writes the result to the output.
+/// Emitted stages are written to stdout unless an output file is supplied.
+pub fn compile(sess : &mut Session, opts : &Options) -> Option<()> {
+    match (&opts.emit, &opts.backend) {
+        (Some(Emit::Bitcode), Backend::C) => sess.issues.report_error(CompilerError::new()
+                .reason("LLVM bitcode can only be emitted by the LLVM backend")
+                .note("consider using the LLVM backend (`--backend llvm`)")),
+        (None, Backend::C) => compile_to_c(sess, opts),
+        (Some(emit), Backend::C) if emit.is_native() => compile_to_c(sess, opts),
+        (Some(emit), Backend::LLVM) if emit.is_native() => compile_to_llvm(sess, opts),
+        (emit, backend) => {
+            let emit = emit.clone().unwrap_or(match backend {
+                Backend::C => Emit::C,
+                Backend::LLVM => Emit::LLVM
+            });
+            let out = run_pipeline(sess, opts, &emit)?;
+            write_output(sess, opts.output.as_deref(), &out)
+        }
+    }
+}
+
+/// Compiles this program into a C source file. If a native file is requested, then the
+/// system C compiler is invoked on the generated file.
+pub fn compile_to_c(sess : &mut Session, opts : &Options) -> Option<()> {
+    let out = run_pipeline(sess, opts, &Emit::C)?;
+    let (flag, extension) = match opts.emit {
+        Some(Emit::Asm) => (Some("-S"), "s"),
+        Some(Emit::Obj) => (Some("-c"), "o"),
+        Some(Emit::Exe) => (None, env::consts::EXE_EXTENSION),
+        _ => {
+            let c_path = output_path(sess, opts.output.as_deref(), "c");
+            return write_output(sess, Some(&c_path), &out);
+        }
+    };
+    let c_path = output_path(sess, None, "c");
+    write_output(sess, Some(&c_path), &out)?;
+    let out_path = output_path(sess, opts.output.as_deref(), extension);
+    let mut args = vec![c_path, "-o".to_string(), out_path, format!("-O{}", opts.opt_level)];
+    if let Some(flag) = flag {
+        args.push(flag.to_string());
+    }
+    invoke_cc(sess, &args)
+}
+
+/// Compiles this program using the LLVM backend, and writes the native file to the output.
+/// Executables are linked by invoking the system C compiler on the generated object file.
+pub fn compile_to_llvm(sess : &mut Session, opts : &Options) -> Option<()> {
+    let ir = generate_ir(sess)?;
+    let (kind, extension) = match opts.emit {
+        Some(Emit::Bitcode) => (llvm::FileKind::Bitcode, "bc"),
+        Some(Emit::Asm) => (llvm::FileKind::Assembly, "s"),
+        Some(Emit::Obj) => (llvm::FileKind::Object, "o"),
+        Some(Emit::Exe) => {
+            let obj_path = output_path(sess, None, "o");
+            llvm::compile_to_file(ir, &sess.src, &mut sess.issues,
+                    llvm::FileKind::Object, &obj_path, opts.opt_level)?;
+            let exe_path = output_path(sess, opts.output.as_deref(), env::consts::EXE_EXTENSION);
+            return invoke_cc(sess, &[obj_path, "-o".to_string(), exe_path]);
+        },
+        _ => return sess.issues.report_error(
+                CompilerError::unreachable("non-native emit stage"))
+    };
+    let path = output_path(sess, opts.output.as_deref(), extension);
+    llvm::compile_to_file(ir, &sess.src, &mut sess.issues, kind, &path, opts.opt_level)
+}
+
+/// Runs this program in-process using the LLVM JIT and prints its value to stdout.
+pub fn run(sess : &mut Session, opts : &Options) -> Option<()> {
+    let ir = generate_ir(sess)?;
+    let value = llvm::run_jit(ir, &sess.src, &mut sess.issues, opts.opt_level)?;
+    write_output(sess, None, &format!("{}\n", value))
+}
+
+/// Evaluates this program using the IR interpreter and prints its value to stdout.
+pub fn eval(sess : &mut Session) -> Option<()> {
+    let ir = generate_ir(sess)?;
+    let value = cosyir::eval::evaluate(&ir, &sess.src, &mut sess.issues)?;
+    write_output(sess, None, &format!("{}\n", value))
+}
+
+/// Invokes the system C compiler with these arguments. The compiler defaults to `cc`, but
+/// can be changed using the `CC` environment variable.
+fn invoke_cc(sess : &mut Session, args : &[String]) -> Option<()> {
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(&cc)
+            .args(args)
+            .status();
+    match status {
+        Ok(status) if status.success() => Some(()),
+        Ok(status) => sess.issues.report_error(CompilerError::new()
+                .reason(format!("the C compiler `{}` failed ({})", cc, status))
+                .note("this may be caused by a bug with the backend")),
+        Err(e) => sess.issues.report_error(CompilerError::new()
+                .reason(format!("unable to invoke the C compiler `{}`", cc))
+                .note(e)
+                .note("consider setting the `CC` environment variable to a valid C compiler"))
+    }
+}
+
+/// Parses and type checks the source of this session.
+fn generate_ir(sess : &mut Session) -> Option<cosyir::ir::Module> {
     let ast = parse::build_ast(&sess.src, &mut sess.issues)?;
+    cosyir::generate_ir(ast, &sess.src, &mut sess.issues)
+}
+
+/// Runs the compiler pipeline until the emit stage is reached, and returns the output of that stage.
+fn run_pipeline(sess : &mut Session, opts : &Options, emit : &Emit) -> Option<String> {
+    if let Emit::Tokens = emit {
+        return Some(dump::tokens(&sess.src));
+    }
+    let ast = parse::build_ast(&sess.src, &mut sess.issues)?;
+    if let Emit::Ast = emit {
+        return Some(dump::ast(&ast, &sess.src));
+    }
     let ir = cosyir::generate_ir(ast, &sess.src, &mut sess.issues)?;
-    codegen::compile_ir(ir, &sess.src, &mut sess.issues)?;
-    Some(())
+    let out = match emit {
+        Emit::Ir => dump::ir(&ir, &sess.src),
+        Emit::C => {
+            let mut out = String::new();
+            c::generate_c(ir, &sess.src, &mut sess.issues, &mut out)?;
+            out
+        },
+        Emit::LLVM => llvm::compile_ir(ir, &sess.src, &mut sess.issues, opts.opt_level)?,
+        _ => sess.issues.report_error(
+                CompilerError::unreachable("non-textual emit stage"))?
+    };
+    Some(out)
+}
+
+/// Returns the path of an output file. If no path is supplied, then the path of the
+/// session is used with its extension replaced by `extension`.
+fn output_path(sess : &Session, output : Option<&str>, extension : &str) -> String {
+    if let Some(path) = output {
+        return path.to_string();
+    }
+    let path = Path::new(&sess.filepath)
+            .with_extension(extension)
+            .to_string_lossy()
+            .into_owned();
+    if path == sess.filepath {
+        // avoid overwriting the source file
+        format!("{}.out", path)
+    } else {
+        path
+    }
 }
 
-pub fn test() {
-    let mut sess = open("examples/test.cosy");
-    compile(&mut sess);
-    if sess.errors_occurred() {
-        println!("{}", sess);
+/// Writes a string to this output file, or to stdout if `None`.
+fn write_output(sess : &mut Session, output : Option<&str>, out : &str) -> Option<()> {
+    let result = if let Some(path) = output {
+        fs::write(path, out)
+    } else {
+        io::stdout().write_all(out.as_bytes())
+    };
+    if let Err(e) = result {
+        let filename = output.unwrap_or("<stdout>");
+        sess.issues.report_error(CompilerError::new()
+                .reason(format!("unable to write to a file with the name `{}`", filename))
+                .note(e))
+    } else {
+        Some(())
     }
 }
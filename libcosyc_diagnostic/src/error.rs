@@ -88,17 +88,51 @@ impl CompilerError {
     }
 }
 
+/// Represents different ways of handling warnings.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum WarningMode {
+    /// Warnings are reported as normal.
+    Allow,
+    /// Warnings are discarded.
+    Ignore,
+    /// Warnings are promoted to fatal errors.
+    Deny
+}
+
+impl Default for WarningMode {
+    fn default() -> Self {
+        Self::Allow
+    }
+}
+
 /// Records any issues that occurred, including the highest error level achieved.
 #[derive(Default)]
 pub struct IssueTracker {
     errors : Vec<CompilerError>,
-    error_level : ErrorLevel
+    error_level : ErrorLevel,
+    warning_mode : WarningMode
 }
 
 impl IssueTracker {
+    /// Sets how the issue tracker should handle warnings.
+    pub fn set_warning_mode(&mut self, mode : WarningMode) {
+        self.warning_mode = mode;
+    }
+
     /// Reports an error to the issue tracker.
     /// Always returns `None`, which can be used to early-exit if necessary.
-    pub fn report_error<T>(&mut self, error : CompilerError) -> Option<T> {
+    pub fn report_error<T>(&mut self, mut error : CompilerError) -> Option<T> {
+        if error.level == ErrorLevel::Warning {
+            match self.warning_mode {
+                WarningMode::Allow => (),
+                WarningMode::Ignore => return None,
+                WarningMode::Deny => {
+                    error = error
+                            .level(ErrorLevel::Fatal)
+                            .note("warnings are currently being treated as errors");
+                }
+            }
+        }
         if error.level > self.error_level {
             self.error_level = error.level.clone();
         }
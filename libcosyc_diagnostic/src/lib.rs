@@ -1,7 +1,7 @@
 pub mod source;
 pub mod error;
 
-use error::{ IssueTracker, CompilerError };
+use error::{ IssueTracker, CompilerError, ErrorLevel };
 use source::Span;
 use std::{ fmt, fs };
 
@@ -53,8 +53,16 @@ impl Session {
         sess
     }
 
-    /// Returns whether errors occurred in the current session.
+    /// Returns whether fatal errors occurred in the current session.
     pub fn errors_occurred(&self) -> bool {
+        self.issues
+                .get_errors()
+                .iter()
+                .any(|error| error.level == ErrorLevel::Fatal)
+    }
+
+    /// Returns whether any errors or warnings occurred in the current session.
+    pub fn issues_occurred(&self) -> bool {
         !self.issues.get_errors().is_empty()
     }
 }
@@ -62,7 +70,7 @@ impl Session {
 impl fmt::Display for Session {
     fn fmt(&self, out : &mut fmt::Formatter) -> fmt::Result {
         // it works, i don't care if it's trash
-        if self.errors_occurred() {
+        if self.issues_occurred() {
             let newlines = source::prospect_newlines(&self.src);
             for error in self.issues.get_errors() {
                 let level_str = format!("{:?}", error.level);
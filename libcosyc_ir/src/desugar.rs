@@ -1,14 +1,17 @@
 use crate::ir;
 use libcosyc_diagnostic::{
-    source::Renderable,
+    source::{ Span, Renderable },
     error::{ CompilerError, IssueTracker, Failable }
 };
 use libcosyc_parse::syntax as ast;
+use std::convert::TryFrom;
 
 /// Manages the conversion of AST terms into IR instructions.
 pub struct IRBuilder<'a> {
     src : &'a str,
-    issues : &'a mut IssueTracker
+    issues : &'a mut IssueTracker,
+    next_local : usize,
+    owner : Option<Span>
 }
 
 impl Failable for IRBuilder<'_> {
@@ -26,25 +29,285 @@ impl Renderable for IRBuilder<'_> {
 impl<'a> IRBuilder<'a> {
     /// Creates a new instance from this issue tracker and source file.
     pub fn new(src : &'a str, issues : &'a mut IssueTracker) -> Self {
-        Self { src, issues }
+        let next_local = 0;
+        let owner = None;
+        Self { src, issues, next_local, owner }
     }
 
-    /// Generates instructions from AST terms.
+    /// Returns the next local id.
+    pub fn get_next_local(&mut self) -> usize {
+        let local = self.next_local;
+        self.next_local += 1;
+        local
+    }
+
+    /// Generates the items of a module. The items of impl blocks are added to the module,
+    /// and are owned by the type of their block.
+    pub fn desugar_module(&mut self, module : ast::Module) -> Option<ir::Module> {
+        let mut items = Vec::new();
+        for item in module.items {
+            if let ast::ItemKind::Impl { items : impl_items } = item.kind {
+                self.owner = Some(item.name);
+                for impl_item in impl_items {
+                    items.push(self.desugar_item(impl_item)?);
+                }
+                self.owner = None;
+            } else {
+                items.push(self.desugar_item(item)?);
+            }
+        }
+        Some(ir::Module { items })
+    }
+
+    /// Generates a top-level item. Function parameters are assigned new local ids.
+    pub fn desugar_item(&mut self, item : ast::Item) -> Option<ir::Item> {
+        let kind = match item.kind {
+            ast::ItemKind::Function { params, return_type, body } => {
+                let mut new_params = Vec::new();
+                for param in params {
+                    let id = self.get_next_local();
+                    let datatype = self.desugar_type(param.datatype)?;
+                    new_params.push(ir::Param { id, name : param.name, datatype });
+                }
+                let return_type = self.desugar_type(*return_type)?;
+                let body = self.desugar(*body)?;
+                ir::ItemKind::Function { params : new_params, return_type, body }
+            },
+            ast::ItemKind::Definition { datatype, value } => {
+                let mut value = self.desugar(*value)?;
+                value.datatype = self.desugar_type(*datatype)?;
+                ir::ItemKind::Definition { value }
+            },
+            ast::ItemKind::Data { datatype } => if let ast::TypeKind::Struct { fields } = datatype.kind {
+                let mut new_fields = Vec::new();
+                for field in fields {
+                    let datatype = self.desugar_type(field.datatype)?;
+                    new_fields.push(ir::Field { name : field.name, datatype });
+                }
+                ir::ItemKind::Struct { fields : new_fields }
+            } else {
+                let datatype = self.desugar_type(*datatype)?;
+                ir::ItemKind::Data { datatype }
+            },
+            ast::ItemKind::Impl { .. } => self.report(CompilerError::new()
+                    .reason("impl blocks cannot be nested")
+                    .span(&item.span))?
+        };
+        let owner = self.owner.as_ref().map(|name| ir::InstType::new(name.clone(),
+                ir::TypeKind::Variable { name : name.clone() }));
+        Some(ir::Item { span : item.span, name : item.name, owner, kind })
+    }
+
+    /// Generates a type from an AST type. Inside impl blocks, the type `self` refers to the
+    /// type of the block. The empty tuple `()` is the type of statements.
+    pub fn desugar_type(&mut self, ty : ast::Type) -> Option<ir::InstType> {
+        let kind = match ty.kind {
+            ast::TypeKind::Named => match &self.owner {
+                Some(owner) if self.render(&ty.span) == "self" => ir::TypeKind::Variable { name : owner.clone() },
+                _ => ir::TypeKind::Variable { name : ty.span.clone() }
+            },
+            ast::TypeKind::Pointer { datatype } => {
+                let datatype = self.desugar_type(*datatype)?;
+                ir::TypeKind::Pointer(Box::new(datatype.kind))
+            },
+            ast::TypeKind::Function { params, return_type } => {
+                let mut new_params = Vec::new();
+                for param in params {
+                    new_params.push(self.desugar_type(param)?.kind);
+                }
+                let ret = Box::new(self.desugar_type(*return_type)?.kind);
+                ir::TypeKind::Function { params : new_params, ret }
+            },
+            ast::TypeKind::Tuple { elements } if elements.is_empty() => ir::TypeKind::Empty,
+            ast::TypeKind::Tuple { elements } => {
+                let mut new_elements = Vec::new();
+                for element in elements {
+                    new_elements.push(self.desugar_type(element)?.kind);
+                }
+                ir::TypeKind::Tuple(new_elements)
+            },
+            ast::TypeKind::Array { element, length } => {
+                let element = Box::new(self.desugar_type(*element)?.kind);
+                let length = self.desugar_array_length(*length)?;
+                ir::TypeKind::Array { element, length }
+            },
+            ast::TypeKind::Struct { .. } => self.report(CompilerError::new()
+                    .reason("anonymous struct types are not supported")
+                    .span(&ty.span)
+                    .note("consider giving this struct a name using `data T = struct { ... }`"))?
+        };
+        Some(ir::InstType::new(ty.span, kind))
+    }
+
+    /// Parses the characters of a quoted literal, replacing its escape sequences with the
+    /// characters they represent.
+    pub fn desugar_escapes(&mut self, span : &Span) -> Option<Vec<char>> {
+        let literal = self.render(span);
+        let contents = &literal[1..literal.len() - 1];
+        let offset = span.begin + 1;
+        let mut value = Vec::new();
+        let mut chars = contents.char_indices().peekable();
+        while let Some((begin, c)) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+            let mut code_point = None;
+            let escape = match chars.next().map(|(_, c)| c) {
+                Some('n') => Some('\n'),
+                Some('t') => Some('\t'),
+                Some('r') => Some('\r'),
+                Some('0') => Some('\0'),
+                Some('\\') => Some('\\'),
+                Some('"') => Some('"'),
+                Some('\'') => Some('\''),
+                Some('u') => {
+                    let mut digits = String::new();
+                    let mut closed = false;
+                    if let Some((_, '{')) = chars.peek() {
+                        chars.next();
+                        for (_, c) in chars.by_ref() {
+                            if c == '}' {
+                                closed = true;
+                                break;
+                            }
+                            digits.push(c);
+                        }
+                    }
+                    if closed && !digits.is_empty() && digits.len() <= 6 {
+                        code_point = u32::from_str_radix(&digits, 16).ok();
+                    }
+                    code_point.and_then(char::from_u32)
+                },
+                _ => None
+            };
+            let end = chars.peek().map_or(contents.len(), |(end, _)| *end);
+            let escape_span = Span::new(offset + begin, offset + end);
+            match escape {
+                Some(c) => value.push(c),
+                None if code_point.is_some() => return self.report(CompilerError::new()
+                        .reason(format!("invalid unicode escape `{}`", escape_span.render(self.src)))
+                        .span(&escape_span)
+                        .note("surrogate code points `D800` to `DFFF` and code points above `10FFFF` are not unicode scalar values")),
+                None if contents[begin..].starts_with("\\u") => return self.report(CompilerError::new()
+                        .reason(format!("invalid unicode escape `{}`", escape_span.render(self.src)))
+                        .span(&escape_span)
+                        .note("unicode escapes are written `\\u{...}`, using up to 6 hexadecimal digits for a valid unicode code point")),
+                None => return self.report(CompilerError::new()
+                        .reason(format!("unknown escape sequence `{}`", escape_span.render(self.src)))
+                        .span(&escape_span)
+                        .note("valid escape sequences are `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"`, `\\'` and `\\u{...}`"))
+            }
+        }
+        Some(value)
+    }
+
+    /// Parses the bytes of a string literal, which are the UTF-8 encoding of its characters.
+    pub fn desugar_string(&mut self, span : &Span) -> Option<Vec<u8>> {
+        let mut value = Vec::new();
+        for c in self.desugar_escapes(span)? {
+            let mut bytes = [0; 4];
+            value.extend_from_slice(c.encode_utf8(&mut bytes).as_bytes());
+        }
+        Some(value)
+    }
+
+    /// Parses a character literal, which must contain exactly one character.
+    pub fn desugar_char(&mut self, span : &Span) -> Option<char> {
+        match self.desugar_escapes(span)?.as_slice() {
+            [c] => Some(*c),
+            [] => self.report(CompilerError::new()
+                    .reason("empty character literal")
+                    .span(span)),
+            chars => self.report(CompilerError::new()
+                    .reason(format!("character literal contains {} characters", chars.len()))
+                    .span(span)
+                    .note("consider using a string literal `\"...\"` instead"))
+        }
+    }
+
+    /// Returns the length of an array type, which must be an integer literal.
+    pub fn desugar_array_length(&mut self, term : ast::Term) -> Option<u64> {
+        let value = if let ast::TermKind::Integral { radix } = term.kind {
+            self.desugar_integral(&term.span, radix)?
+        } else {
+            return self.report(CompilerError::new()
+                    .reason("expected an integer literal as the length of this array type")
+                    .span(&term.span));
+        };
+        if let Ok(length) = u64::try_from(value) {
+            Some(length)
+        } else {
+            self.report(CompilerError::new()
+                    .reason("array length is too large")
+                    .span(&term.span))
+        }
+    }
+
+    /// Parses the value of an integer literal, ignoring its radix prefix and any `_` separators.
+    pub fn desugar_integral(&mut self, span : &Span, radix : u8) -> Option<i128> {
+        let literal = self.render(span);
+        let prefix = if radix == 10 { "" } else { &literal[..2] };
+        let digits = literal[prefix.len()..].replace('_', "");
+        if let Some(digit) = digits.chars().find(|x| !x.is_digit(radix as u32)) {
+            return self.report(CompilerError::new()
+                    .span(span)
+                    .reason(format!("invalid digit `{}` in base-{} integer literal", digit, radix)));
+        }
+        if digits.is_empty() {
+            return self.report(CompilerError::new()
+                    .span(span)
+                    .reason(format!("expected digits after the `{}` prefix", prefix)));
+        }
+        match i128::from_str_radix(&digits, radix as u32) {
+            Ok(value) => Some(value),
+            Err(_) => self.report(CompilerError::new()
+                    .span(span)
+                    .reason("integer literal is too large"))
+        }
+    }
+
+    /// Parses the value of a floating point literal, ignoring any `_` separators.
+    pub fn desugar_float(&mut self, span : &Span) -> Option<f64> {
+        let digits = self.render(span).replace('_', "");
+        match digits.parse::<f64>() {
+            Ok(value) if value.is_finite() => Some(value),
+            Ok(_) => self.report(CompilerError::new()
+                    .span(span)
+                    .reason("floating point literal is too large")),
+            Err(_) => self.report(CompilerError::new()
+                    .span(span)
+                    .reason("invalid floating point literal")
+                    .note("floating point literals are written using decimal digits, such as `1.5`, `15e-1` or `0.15e1`"))
+        }
+    }
+
+    /// Generates instructions from AST terms. While loops are desugared into loops whose body
+    /// breaks once the condition no longer holds.
     pub fn desugar(&mut self, term : ast::Term) -> Option<ir::Inst> {
         let span = term.span;
         let kind = match term.kind {
             ast::TermKind::Variable => ir::InstKind::Variable,
-            ast::TermKind::Integral { radix } => ir::InstKind::Integral { radix },
+            ast::TermKind::Integral { radix } => {
+                let value = self.desugar_integral(&span, radix)?;
+                ir::InstKind::Integral { value }
+            },
+            ast::TermKind::Float => {
+                let value = self.desugar_float(&span)?;
+                ir::InstKind::Float { value }
+            },
+            ast::TermKind::Boolean { value } => ir::InstKind::Boolean { value },
+            ast::TermKind::Char => {
+                let value = self.desugar_char(&span)?;
+                ir::InstKind::Char { value }
+            },
+            ast::TermKind::String => {
+                let value = self.desugar_string(&span)?;
+                ir::InstKind::String { value }
+            },
             ast::TermKind::TypeAnno { value, datatype } => {
                 let mut value = self.desugar(*value)?;
-                let datatype = self.desugar(*datatype)?;
-                let kind = match datatype.kind {
-                    ir::InstKind::Variable => ir::TypeKind::Variable,
-                    _ => self.report(CompilerError::new()
-                            .reason("invalid type expression")
-                            .span(&datatype.span))?
-                };
-                value.datatype = ir::InstType::new(datatype.span, kind);
+                value.datatype = self.desugar_type(*datatype)?;
                 return Some(value);
             },
             ast::TermKind::BinaryOp { op, left, right } => {
@@ -56,13 +319,205 @@ impl<'a> IRBuilder<'a> {
                 let callsite = Box::new(ir::Inst::new(op, ir::InstKind::Variable));
                 let args = vec![self.desugar(*value)?];
                 ir::InstKind::FunctionApp { callsite, args }
+            },
+            ast::TermKind::Call { callsite, args } => {
+                let mut new_args = Vec::new();
+                let callsite = if let ast::TermKind::Field { value, field } = callsite.kind {
+                    new_args.push(self.desugar(*value)?);
+                    Box::new(ir::Inst::new(field.clone(), ir::InstKind::Method { name : field }))
+                } else {
+                    Box::new(self.desugar(*callsite)?)
+                };
+                for arg in args {
+                    new_args.push(self.desugar(arg)?);
+                }
+                ir::InstKind::FunctionApp { callsite, args : new_args }
+            },
+            ast::TermKind::StructLit { fields } => {
+                let mut new_fields = Vec::new();
+                for field in fields {
+                    new_fields.push((field.name, 0, self.desugar(field.term)?));
+                }
+                ir::InstKind::Struct { fields : new_fields }
+            },
+            ast::TermKind::Field { value, field } => {
+                let value = Box::new(self.desugar(*value)?);
+                ir::InstKind::Field { value, name : field, index : 0 }
+            },
+            ast::TermKind::Associated { datatype, name } => {
+                let datatype = self.desugar_type(*datatype)?;
+                ir::InstKind::Associated { datatype, name }
+            },
+            ast::TermKind::Reference { value } => {
+                let value = Box::new(self.desugar(*value)?);
+                ir::InstKind::Reference { value }
+            },
+            ast::TermKind::Dereference { value } => {
+                let value = Box::new(self.desugar(*value)?);
+                ir::InstKind::Dereference { value }
+            },
+            ast::TermKind::Cast { value, datatype } => {
+                let value = Box::new(self.desugar(*value)?);
+                let target = self.desugar_type(*datatype)?;
+                ir::InstKind::Cast { value, target }
+            },
+            ast::TermKind::Block { stmts, value } => {
+                let mut new_stmts = Vec::new();
+                for stmt in stmts {
+                    new_stmts.push(self.desugar(stmt)?);
+                }
+                let value = if let Some(value) = value {
+                    Some(Box::new(self.desugar(*value)?))
+                } else {
+                    None
+                };
+                ir::InstKind::Block { stmts : new_stmts, value }
+            },
+            ast::TermKind::If { condition, on_true, on_false } => {
+                let condition = Box::new(self.desugar(*condition)?);
+                let on_true = Box::new(self.desugar(*on_true)?);
+                let on_false = Box::new(if let Some(on_false) = on_false {
+                    self.desugar(*on_false)?
+                } else {
+                    let kind = ir::InstKind::Block { stmts : Vec::new(), value : None };
+                    ir::Inst::new(span.clone(), kind)
+                });
+                ir::InstKind::If { condition, on_true, on_false }
+            },
+            ast::TermKind::Assign { target, value } => {
+                let target = Box::new(self.desugar(*target)?);
+                let value = Box::new(self.desugar(*value)?);
+                ir::InstKind::Assign { target, value }
+            },
+            ast::TermKind::While { condition, body } => {
+                let condition = Box::new(self.desugar(*condition)?);
+                let on_true = Box::new(self.desugar(*body)?);
+                let on_false = Box::new(ir::Inst::new(span.clone(), ir::InstKind::Break));
+                let kind = ir::InstKind::If { condition, on_true, on_false };
+                let body = Box::new(ir::Inst::new(span.clone(), kind));
+                ir::InstKind::Loop { body }
+            },
+            ast::TermKind::Loop { body } => {
+                let body = Box::new(self.desugar(*body)?);
+                ir::InstKind::Loop { body }
+            },
+            ast::TermKind::Break => ir::InstKind::Break,
+            ast::TermKind::Continue => ir::InstKind::Continue,
+            ast::TermKind::Let { name, mutable, value, body } => {
+                let id = self.get_next_local();
+                let value = Box::new(self.desugar(*value)?);
+                let body = Box::new(self.desugar(*body)?);
+                ir::InstKind::Let { id, name, mutable, value, body }
             }
         };
         Some(ir::Inst::new(span, kind))
     }
 }
 
-/// Desugars an AST into an IR instruction.
-pub fn surface_into_core(term : ast::Term, src : &str, issues : &mut IssueTracker) -> Option<ir::Inst> {
-    IRBuilder::new(src, issues).desugar(term)
+/// Desugars an AST into IR.
+pub fn surface_into_core(module : ast::Module, src : &str, issues : &mut IssueTracker) -> Option<ir::Module> {
+    IRBuilder::new(src, issues).desugar_module(module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses this integer literal, and returns its value or the reason of the error reported.
+    fn integral(literal : &str, radix : u8) -> Result<i128, String> {
+        let mut issues = IssueTracker::default();
+        let span = Span::new(0, literal.len());
+        let value = IRBuilder::new(literal, &mut issues).desugar_integral(&span, radix);
+        value.ok_or_else(|| issues.get_errors()[0].reason.clone())
+    }
+
+    #[test]
+    fn integral_literals_use_their_radix() {
+        assert_eq!(integral("42", 10), Ok(42));
+        assert_eq!(integral("0xFF", 16), Ok(255));
+        assert_eq!(integral("0XfF", 16), Ok(255));
+        assert_eq!(integral("0o777", 8), Ok(511));
+        assert_eq!(integral("0b1010", 2), Ok(10));
+    }
+
+    #[test]
+    fn integral_literals_ignore_separators() {
+        assert_eq!(integral("1_000_000", 10), Ok(1_000_000));
+        assert_eq!(integral("0b1010_1010", 2), Ok(170));
+        assert_eq!(integral("0x_FF_FF", 16), Ok(65535));
+    }
+
+    #[test]
+    fn invalid_integral_literals_are_reported() {
+        assert_eq!(integral("0b102", 2), Err("invalid digit `2` in base-2 integer literal".to_string()));
+        assert_eq!(integral("0o8", 8), Err("invalid digit `8` in base-8 integer literal".to_string()));
+        assert_eq!(integral("12a", 10), Err("invalid digit `a` in base-10 integer literal".to_string()));
+        assert_eq!(integral("0x", 16), Err("expected digits after the `0x` prefix".to_string()));
+        assert_eq!(integral("0x__", 16), Err("expected digits after the `0x` prefix".to_string()));
+    }
+
+    /// Parses the string literal at this span of the source, and returns its bytes or the
+    /// reason and span of the error reported.
+    fn string(src : &str, span : Span) -> Result<Vec<u8>, (String, Span)> {
+        let mut issues = IssueTracker::default();
+        let value = IRBuilder::new(src, &mut issues).desugar_string(&span);
+        value.ok_or_else(|| {
+            let err = &issues.get_errors()[0];
+            (err.reason.clone(), err.span.clone().unwrap_or_default())
+        })
+    }
+
+    #[test]
+    fn escape_sequences_are_replaced() {
+        let src = r#""a\n\t\"\\\'\0""#;
+        assert_eq!(string(src, Span::new(0, src.len())), Ok(b"a\n\t\"\\'\0".to_vec()));
+        let src = r#""\u{48}\u{e9}\u{1F600}""#;
+        assert_eq!(string(src, Span::new(0, src.len())), Ok("H\u{e9}\u{1F600}".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn invalid_escape_sequences_are_spanned() {
+        let src = r#"let x = "ab\qc";"#;
+        assert_eq!(string(src, Span::new(8, 15)),
+                Err(("unknown escape sequence `\\q`".to_string(), Span::new(11, 13))));
+        let src = r#""\u{D800}""#;
+        assert_eq!(string(src, Span::new(0, src.len())),
+                Err(("invalid unicode escape `\\u{D800}`".to_string(), Span::new(1, 9))));
+        let src = r#""a\u{110000}""#;
+        assert_eq!(string(src, Span::new(0, src.len())),
+                Err(("invalid unicode escape `\\u{110000}`".to_string(), Span::new(2, 12))));
+        let src = r#""\u12""#;
+        assert_eq!(string(src, Span::new(0, src.len())),
+                Err(("invalid unicode escape `\\u`".to_string(), Span::new(1, 3))));
+    }
+
+    /// Parses this character literal, and returns its value or the reason and span of the
+    /// error reported.
+    fn character(literal : &str) -> Result<char, (String, Span)> {
+        let mut issues = IssueTracker::default();
+        let span = Span::new(0, literal.len());
+        let value = IRBuilder::new(literal, &mut issues).desugar_char(&span);
+        value.ok_or_else(|| {
+            let err = &issues.get_errors()[0];
+            (err.reason.clone(), err.span.clone().unwrap_or_default())
+        })
+    }
+
+    #[test]
+    fn character_literals_contain_one_character() {
+        assert_eq!(character("'a'"), Ok('a'));
+        assert_eq!(character("'\\n'"), Ok('\n'));
+        assert_eq!(character("'\\''"), Ok('\''));
+        assert_eq!(character("'\u{e9}'"), Ok('\u{e9}'));
+        assert_eq!(character("'\\u{1F600}'"), Ok('\u{1F600}'));
+    }
+
+    #[test]
+    fn invalid_character_literals_are_spanned() {
+        assert_eq!(character("''"), Err(("empty character literal".to_string(), Span::new(0, 2))));
+        assert_eq!(character("'ab'"), Err(("character literal contains 2 characters".to_string(), Span::new(0, 4))));
+        assert_eq!(character("'\\n\\t'"), Err(("character literal contains 2 characters".to_string(), Span::new(0, 6))));
+        assert_eq!(character("'\\x'"), Err(("unknown escape sequence `\\x`".to_string(), Span::new(1, 3))));
+        assert_eq!(character("'\\u{DFFF}'"), Err(("invalid unicode escape `\\u{DFFF}`".to_string(), Span::new(1, 9))));
+    }
 }
@@ -0,0 +1,640 @@
+use crate::ir;
+use libcosyc_diagnostic::{
+    error::{ CompilerError, IssueTracker, Failable },
+    source::{ Span, Renderable }
+};
+use std::{ fmt, mem, io::{ self, Write }, collections::{ HashMap, HashSet } };
+
+/// The maximum number of nested calls before evaluation is aborted.
+pub const MAX_CALL_DEPTH : usize = 1000;
+
+/// Represents a value produced by the interpreter.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    /// An integer of a specific fixed-width type.
+    Integral {
+        value : i128,
+        datatype : ir::TypeKind
+    },
+    /// A floating point number, which is rounded to the precision of its type.
+    Float {
+        value : f64,
+        datatype : ir::TypeKind
+    },
+    /// Either `true` or `false`.
+    Bool {
+        value : bool
+    },
+    /// A unicode scalar value.
+    Char {
+        value : char
+    },
+    /// A string of bytes.
+    Str {
+        value : Vec<u8>
+    },
+    /// A struct, with its fields in declaration order.
+    Struct {
+        fields : Vec<(String, Value)>
+    },
+    /// The value of blocks without a value.
+    Empty,
+    /// A pointer to the memory cell with this address.
+    Pointer {
+        address : usize
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, out : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Integral { value, .. } => write!(out, "{}", value),
+            Self::Float { value, datatype : ir::TypeKind::Float(32) } => write!(out, "{:?}", *value as f32),
+            Self::Float { value, .. } => write!(out, "{:?}", value),
+            Self::Bool { value } => write!(out, "{}", value),
+            Self::Char { value } => write!(out, "{:?}", value),
+            Self::Str { value } => write!(out, "{:?}", String::from_utf8_lossy(value)),
+            Self::Struct { fields } => {
+                write!(out, "struct {{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    write!(out, "{} {} = {}", if i == 0 { "" } else { "," }, name, value)?;
+                }
+                write!(out, " }}")
+            },
+            Self::Empty => write!(out, "()"),
+            Self::Pointer { address } => write!(out, "^{:#x}", address)
+        }
+    }
+}
+
+/// Represents a jump out of the body of the innermost loop.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Jump {
+    Break,
+    Continue
+}
+
+/// Returns the inclusive range of values that can be represented by this integer type.
+pub fn int_range(ty : &ir::TypeKind) -> Option<(i128, i128)> {
+    match ty {
+        ir::TypeKind::Int(n) if *n > 0 && *n <= 64 => {
+            let max = (1i128 << (n - 1)) - 1;
+            Some((-max - 1, max))
+        },
+        ir::TypeKind::UInt(n) if *n > 0 && *n <= 64 => Some((0, (1i128 << n) - 1)),
+        _ => None
+    }
+}
+
+/// Wraps this integer around the range of values that can be represented by this integer type,
+/// keeping only its lowest bits. Since values are stored without their representation, this
+/// also sign-extends signed values and zero-extends unsigned values.
+pub fn wrap_int(value : i128, ty : &ir::TypeKind) -> i128 {
+    match ty {
+        ir::TypeKind::Int(n) | ir::TypeKind::UInt(n) if *n > 0 && *n < 128 => {
+            let modulus = 1i128 << n;
+            let value = value.rem_euclid(modulus);
+            if matches!(ty, ir::TypeKind::Int(_)) && value >= modulus / 2 {
+                value - modulus
+            } else {
+                value
+            }
+        },
+        _ => value
+    }
+}
+
+/// Manages the evaluation of IR. Local variables are stored in memory cells, so that
+/// their addresses can be taken and they can be assigned to. Jumps out of a loop body are
+/// evaluated to `None`, with the kind of jump stored in `jump`.
+pub struct Interpreter<'a> {
+    src : &'a str,
+    issues : &'a mut IssueTracker,
+    module : &'a ir::Module,
+    locals : HashMap<usize, usize>,
+    mutable : HashSet<usize>,
+    memory : Vec<Value>,
+    depth : usize,
+    jump : Option<Jump>
+}
+
+impl Failable for Interpreter<'_> {
+    fn issues(&mut self) -> &mut IssueTracker {
+        self.issues
+    }
+}
+
+impl Renderable for Interpreter<'_> {
+    fn src(&self) -> &str {
+        self.src
+    }
+}
+
+impl<'a> Interpreter<'a> {
+    /// Creates a new instance from this issue tracker and source file, which evaluates
+    /// the items of this module.
+    pub fn new(module : &'a ir::Module, src : &'a str, issues : &'a mut IssueTracker) -> Self {
+        let locals = HashMap::new();
+        let mutable = HashSet::new();
+        let memory = Vec::new();
+        let depth = 0;
+        let jump = None;
+        Self { src, issues, module, locals, mutable, memory, depth, jump }
+    }
+
+    /// Stores this value in a new memory cell, and returns its address.
+    pub fn allocate(&mut self, value : Value) -> usize {
+        self.memory.push(value);
+        self.memory.len() - 1
+    }
+
+    /// Calls the item with this id using these arguments, and returns its value.
+    /// Definitions are evaluated in the same way as functions without parameters.
+    /// Any memory allocated by the call is freed when it returns.
+    pub fn eval_item(&mut self, id : usize, args : Vec<Value>, span : &Span) -> Option<Value> {
+        if self.depth >= MAX_CALL_DEPTH {
+            return self.report(CompilerError::new()
+                    .span(span)
+                    .reason("stack overflow")
+                    .note(format!("the maximum call depth is {}", MAX_CALL_DEPTH)));
+        }
+        let module = self.module;
+        let (params, body) = match &module.items[id].kind {
+            ir::ItemKind::Function { params, body, .. } => (params.as_slice(), body),
+            ir::ItemKind::Definition { value } => (&[][..], value),
+            ir::ItemKind::Data { .. } | ir::ItemKind::Struct { .. } => return self.report(
+                    CompilerError::unreachable("type as a value").span(span))
+        };
+        let base = self.memory.len();
+        let frame = params.iter()
+                .map(|param| param.id)
+                .zip(args)
+                .map(|(id, arg)| (id, self.allocate(arg)))
+                .collect();
+        let frame = mem::replace(&mut self.locals, frame);
+        self.depth += 1;
+        let value = self.eval(body);
+        self.depth -= 1;
+        self.locals = frame;
+        self.memory.truncate(base);
+        value
+    }
+
+    /// Creates an integer value of this type, and reports an overflow error if it is out of range.
+    pub fn make_int(&mut self, value : i128, datatype : &ir::TypeKind, span : &Span) -> Option<Value> {
+        let (min, max) = if let Some(range) = int_range(datatype) {
+            range
+        } else {
+            return self.report(CompilerError::unreachable(
+                    format!("integer value of type `{}`", datatype)).span(span));
+        };
+        if value < min || value > max {
+            self.report(CompilerError::new()
+                    .span(span)
+                    .reason(format!("integer overflow: the value {} does not fit into `{}`", value, datatype))
+                    .note(format!("values of type `{}` must be between {} and {}", datatype, min, max)))
+        } else {
+            let datatype = datatype.clone();
+            Some(Value::Integral { value, datatype })
+        }
+    }
+
+    /// Creates a floating point value of this type.
+    pub fn make_float(&mut self, value : f64, datatype : &ir::TypeKind) -> Value {
+        let value = if let ir::TypeKind::Float(32) = datatype { value as f32 as f64 } else { value };
+        let datatype = datatype.clone();
+        Value::Float { value, datatype }
+    }
+
+    /// Converts this value into a value of this type, with the semantics of a cast.
+    pub fn eval_cast(&mut self, value : Value, datatype : &ir::TypeKind, span : &Span) -> Option<Value> {
+        let value = match (value, datatype) {
+            (Value::Float { value, .. }, ir::TypeKind::Float(_)) => return Some(self.make_float(value, datatype)),
+            (Value::Integral { value, .. }, ir::TypeKind::Float(_)) => {
+                return Some(self.make_float(value as f64, datatype));
+            },
+            // out of range values saturate, and NaN is converted into zero
+            (Value::Float { value, .. }, _) => if let Some((min, max)) = int_range(datatype) {
+                return self.make_int((value as i128).clamp(min, max), datatype, span);
+            } else {
+                return self.report(CompilerError::unreachable(
+                        format!("conversion of a float into `{}`", datatype)).span(span));
+            },
+            (Value::Integral { value, .. }, ir::TypeKind::Char) => return match char::from_u32(value as u32) {
+                Some(value) => Some(Value::Char { value }),
+                None => self.report(CompilerError::unreachable("cast to an invalid character").span(span))
+            },
+            (Value::Integral { value, .. }, _) => value,
+            (Value::Bool { value }, _) => value as i128,
+            (Value::Char { value }, _) => value as i128,
+            (value, _) => return Some(value)
+        };
+        let value = wrap_int(value, datatype);
+        let datatype = datatype.clone();
+        Some(Value::Integral { value, datatype })
+    }
+
+    /// Evaluates this instruction and returns its value.
+    /// Runtime errors are reported to the issue tracker and result in `None`.
+    pub fn eval(&mut self, inst : &ir::Inst) -> Option<Value> {
+        let span = &inst.span;
+        let datatype = &inst.datatype.kind;
+        match &inst.kind {
+            ir::InstKind::Variable => self.report(
+                    CompilerError::unreachable("unresolved variable").span(span)),
+            ir::InstKind::Local { id } => if let Some(address) = self.locals.get(id) {
+                Some(self.memory[*address].clone())
+            } else {
+                self.report(CompilerError::unreachable("unbound local variable").span(span))
+            },
+            ir::InstKind::Global { id } => self.eval_item(*id, Vec::new(), span),
+            ir::InstKind::Associated { .. } | ir::InstKind::Method { .. } => self.report(
+                    CompilerError::unreachable("unresolved associated item").span(span)),
+            ir::InstKind::Builtin { .. } => self.report(
+                    CompilerError::unimplemented("first-class operators").span(span)),
+            ir::InstKind::Integral { value } => self.make_int(*value, datatype, span),
+            ir::InstKind::Float { value } => Some(self.make_float(*value, datatype)),
+            ir::InstKind::Boolean { value } => Some(Value::Bool { value : *value }),
+            ir::InstKind::Char { value } => Some(Value::Char { value : *value }),
+            ir::InstKind::String { value } => Some(Value::Str { value : value.clone() }),
+            ir::InstKind::FunctionApp { callsite, args } => {
+                let mut values = Vec::new();
+                for arg in args {
+                    values.push(self.eval(arg)?);
+                }
+                self.eval_app(callsite, values, datatype, span)
+            },
+            ir::InstKind::Struct { fields } => {
+                let mut values = Vec::new();
+                for (name, index, value) in fields {
+                    values.push((*index, (self.render(name).to_string(), self.eval(value)?)));
+                }
+                values.sort_by_key(|(index, _)| *index);
+                let fields = values.into_iter()
+                        .map(|(_, field)| field)
+                        .collect();
+                Some(Value::Struct { fields })
+            },
+            ir::InstKind::Field { value, index, .. } => match self.eval(value)? {
+                Value::Struct { mut fields } if *index < fields.len() => Some(fields.swap_remove(*index).1),
+                _ => self.report(CompilerError::unreachable("invalid field access").span(span))
+            },
+            ir::InstKind::Reference { value } => {
+                let address = match value.kind {
+                    ir::InstKind::Local { id } if self.mutable.contains(&id) => {
+                        if let Some(address) = self.locals.get(&id) {
+                            *address
+                        } else {
+                            return self.report(CompilerError::unreachable("unbound local variable").span(span));
+                        }
+                    },
+                    // immutable variables are copied, so that writes through the pointer are not observed
+                    _ => {
+                        let value = self.eval(value)?;
+                        self.allocate(value)
+                    }
+                };
+                Some(Value::Pointer { address })
+            },
+            ir::InstKind::Cast { value, .. } => {
+                let value = self.eval(value)?;
+                self.eval_cast(value, datatype, span)
+            },
+            ir::InstKind::Dereference { value } => match self.eval(value)? {
+                Value::Pointer { address } => if let Some(value) = self.memory.get(address) {
+                    Some(value.clone())
+                } else {
+                    self.report(CompilerError::new()
+                            .span(span)
+                            .reason("attempted to dereference a dangling pointer")
+                            .note("pointers to local variables are invalid once their function returns"))
+                },
+                _ => self.report(CompilerError::unreachable("dereference of a non-pointer").span(span))
+            },
+            ir::InstKind::Block { stmts, value } => {
+                let base = self.memory.len();
+                let result = self.eval_block(stmts, value.as_deref());
+                self.memory.truncate(base);
+                result
+            },
+            ir::InstKind::If { condition, on_true, on_false } => match self.eval(condition)? {
+                Value::Bool { value : true } => self.eval(on_true),
+                Value::Bool { value : false } => self.eval(on_false),
+                _ => self.report(CompilerError::unreachable("non-boolean condition").span(span))
+            },
+            ir::InstKind::Assign { target, value } => {
+                let value = self.eval(value)?;
+                let (address, path) = self.eval_place(target)?;
+                let mut cell = &mut self.memory[address];
+                for index in path {
+                    cell = match cell {
+                        Value::Struct { fields } if index < fields.len() => &mut fields[index].1,
+                        _ => return self.report(CompilerError::unreachable("invalid field access").span(span))
+                    };
+                }
+                *cell = value;
+                Some(Value::Empty)
+            },
+            ir::InstKind::Loop { body } => loop {
+                let base = self.memory.len();
+                let result = self.eval(body);
+                self.memory.truncate(base);
+                if result.is_none() {
+                    match self.jump.take() {
+                        Some(Jump::Break) => break Some(Value::Empty),
+                        Some(Jump::Continue) => (),
+                        None => break None
+                    }
+                }
+            },
+            ir::InstKind::Break => {
+                self.jump = Some(Jump::Break);
+                None
+            },
+            ir::InstKind::Continue => {
+                self.jump = Some(Jump::Continue);
+                None
+            },
+            ir::InstKind::Let { id, mutable, value, body, .. } => {
+                let value = self.eval(value)?;
+                let address = self.allocate(value);
+                self.locals.insert(*id, address);
+                if *mutable {
+                    self.mutable.insert(*id);
+                }
+                self.eval(body)
+            }
+        }
+    }
+
+    /// Returns the address of the memory cell an assignment target refers to, and the field
+    /// indices to follow inside of that cell.
+    fn eval_place(&mut self, inst : &ir::Inst) -> Option<(usize, Vec<usize>)> {
+        let span = &inst.span;
+        match &inst.kind {
+            ir::InstKind::Local { id } => if let Some(address) = self.locals.get(id) {
+                Some((*address, Vec::new()))
+            } else {
+                self.report(CompilerError::unreachable("unbound local variable").span(span))
+            },
+            ir::InstKind::Field { value, index, .. } => {
+                let (address, mut path) = self.eval_place(value)?;
+                path.push(*index);
+                Some((address, path))
+            },
+            ir::InstKind::Dereference { value } => match self.eval(value)? {
+                Value::Pointer { address } if address < self.memory.len() => Some((address, Vec::new())),
+                Value::Pointer { .. } => self.report(CompilerError::new()
+                        .span(span)
+                        .reason("attempted to assign through a dangling pointer")
+                        .note("pointers to local variables are invalid once their function returns")),
+                _ => self.report(CompilerError::unreachable("dereference of a non-pointer").span(span))
+            },
+            _ => self.report(CompilerError::unreachable("invalid assignment target").span(span))
+        }
+    }
+
+    /// Evaluates the statements of a block, and returns its value. Memory allocated by the
+    /// block is freed by the caller.
+    fn eval_block(&mut self, stmts : &[ir::Inst], value : Option<&ir::Inst>) -> Option<Value> {
+        for stmt in stmts {
+            self.eval(stmt)?;
+        }
+        if let Some(value) = value {
+            self.eval(value)
+        } else {
+            Some(Value::Empty)
+        }
+    }
+
+    fn eval_app(&mut self, callsite : &ir::Inst, args : Vec<Value>,
+            datatype : &ir::TypeKind, span : &Span) -> Option<Value> {
+        match callsite.kind {
+            ir::InstKind::Builtin { op } => self.eval_builtin(op, &args, datatype, span),
+            ir::InstKind::Global { id } => self.eval_item(id, args, span),
+            _ => self.report(CompilerError::unimplemented("first-class functions").span(&callsite.span))
+        }
+    }
+
+    /// Evaluates a built-in operator. Reports an error if the operation overflows.
+    pub fn eval_builtin(&mut self, op : ir::BuiltinOp, args : &[Value],
+            datatype : &ir::TypeKind, span : &Span) -> Option<Value> {
+        use ir::BuiltinOp as Op;
+        if let (Op::Print, [Value::Str { value }]) = (op, args) {
+            let mut stdout = io::stdout();
+            if stdout.write_all(value).and_then(|_| stdout.flush()).is_err() {
+                return self.report(CompilerError::new()
+                        .span(span)
+                        .reason("unable to write to the standard output"));
+            }
+            return Some(Value::Empty);
+        }
+        if let [Value::Bool { value : x }, Value::Bool { value : y }] = args {
+            let value = match op {
+                Op::Eq => x == y,
+                Op::Ne => x != y,
+                Op::BitAnd => *x && *y,
+                Op::BitOr => *x || *y,
+                _ => return self.report(CompilerError::unreachable(
+                        format!("application of `{}` to booleans", op)).span(span))
+            };
+            return Some(Value::Bool { value });
+        }
+        match (op, args) {
+            (Op::ToFloat, [value]) | (Op::ToInt, [value]) => return self.eval_cast(value.clone(), datatype, span),
+            (_, [Value::Float { value : x, .. }, Value::Float { value : y, .. }]) if op.is_comparison() => {
+                let value = match op {
+                    Op::Eq => x == y,
+                    Op::Ne => x != y,
+                    Op::Lt => x < y,
+                    Op::Le => x <= y,
+                    Op::Gt => x > y,
+                    _ => x >= y
+                };
+                return Some(Value::Bool { value });
+            },
+            (_, [Value::Float { .. }, ..]) => {
+                let args = args.iter()
+                        .filter_map(|arg| if let Value::Float { value, .. } = arg { Some(*value) } else { None })
+                        .collect::<Vec<_>>();
+                let value = match (op, args.as_slice()) {
+                    (Op::Add, [x, y]) => x + y,
+                    (Op::Sub, [x, y]) => x - y,
+                    (Op::Mul, [x, y]) => x * y,
+                    (Op::Div, [x, y]) => x / y,
+                    (Op::Neg, [x]) => -x,
+                    _ => return self.report(CompilerError::unreachable(
+                            format!("application of `{}` to floating point numbers", op)).span(span))
+                };
+                return Some(self.make_float(value, datatype));
+            },
+            _ => ()
+        }
+        if let [Value::Char { value : x }, Value::Char { value : y }] = args {
+            let value = match op {
+                Op::Eq => x == y,
+                Op::Ne => x != y,
+                Op::Lt => x < y,
+                Op::Le => x <= y,
+                Op::Gt => x > y,
+                Op::Ge => x >= y,
+                _ => return self.report(CompilerError::unreachable(
+                        format!("application of `{}` to characters", op)).span(span))
+            };
+            return Some(Value::Bool { value });
+        }
+        let operand = if let Some(Value::Integral { datatype, .. }) = args.first() {
+            datatype.clone()
+        } else {
+            return self.report(CompilerError::unreachable("built-in operator without integer operands").span(span));
+        };
+        let args = args.iter()
+                .filter_map(|arg| if let Value::Integral { value, .. } = arg { Some(*value) } else { None })
+                .collect::<Vec<_>>();
+        let bits = match operand {
+            ir::TypeKind::Int(n) | ir::TypeKind::UInt(n) => n as i128,
+            _ => 0
+        };
+        let value = match (op, args.as_slice()) {
+            (Op::Add, [x, y]) => x.checked_add(*y),
+            (Op::Sub, [x, y]) => x.checked_sub(*y),
+            (Op::Mul, [x, y]) => x.checked_mul(*y),
+            (Op::Div, [_, 0]) | (Op::Rem, [_, 0]) => return self.report(CompilerError::new()
+                    .span(span)
+                    .reason("attempted to divide by zero")),
+            (Op::Div, [x, y]) => x.checked_div(*y),
+            // the remainder overflows whenever the quotient does, as it does in the backends
+            (Op::Rem, [x, y]) if wrap_int(x / y, &operand) != x / y => None,
+            (Op::Rem, [x, y]) => x.checked_rem(*y),
+            (Op::BitAnd, [x, y]) => Some(x & y),
+            (Op::BitOr, [x, y]) => Some(x | y),
+            (Op::Shl, [_, y]) | (Op::Shr, [_, y]) if *y < 0 || *y >= bits => {
+                return self.report(CompilerError::new()
+                        .span(span)
+                        .reason(format!("attempted to shift a value of type `{}` by {} bits", operand, y)));
+            },
+            // bits shifted past the width of the type are discarded, as they are in the backends
+            (Op::Shl, [x, y]) => Some(wrap_int(x.wrapping_shl(*y as u32), &operand)),
+            (Op::Shr, [x, y]) => Some(x >> y),
+            (Op::Eq, [x, y]) => return Some(Value::Bool { value : x == y }),
+            (Op::Ne, [x, y]) => return Some(Value::Bool { value : x != y }),
+            (Op::Lt, [x, y]) => return Some(Value::Bool { value : x < y }),
+            (Op::Le, [x, y]) => return Some(Value::Bool { value : x <= y }),
+            (Op::Gt, [x, y]) => return Some(Value::Bool { value : x > y }),
+            (Op::Ge, [x, y]) => return Some(Value::Bool { value : x >= y }),
+            (Op::Neg, [x]) => x.checked_neg(),
+            (Op::BitNot, [x]) => if let ir::TypeKind::UInt(_) = operand {
+                int_range(&operand).map(|(_, max)| max - x)
+            } else {
+                Some(!x)
+            },
+            _ => return self.report(CompilerError::unreachable(
+                    format!("application of `{}` to {} argument(s)", op, args.len())).span(span))
+        };
+        let value = if let Some(value) = value {
+            value
+        } else {
+            return self.report(CompilerError::new()
+                    .span(span)
+                    .reason(format!("integer overflow: the result does not fit into `{}`", datatype)));
+        };
+        self.make_int(value, datatype, span)
+    }
+}
+
+/// Evaluates the `main` function of this IR and returns its value.
+pub fn evaluate(module : &ir::Module, src : &str, issues : &mut IssueTracker) -> Option<Value> {
+    let mut interpreter = Interpreter::new(module, src, issues);
+    if let Some(id) = module.find_item("main", src) {
+        let span = module.items[id].name.clone();
+        interpreter.eval_item(id, Vec::new(), &span)
+    } else {
+        interpreter.report(CompilerError::unreachable("program without a `main` function"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libcosyc_parse::build_ast;
+
+    /// Evaluates the `main` function of this source, and returns its value or the reason of
+    /// the first error reported.
+    fn run(src : &str) -> Result<String, String> {
+        let mut issues = IssueTracker::default();
+        let value = build_ast(src, &mut issues)
+                .and_then(|ast| crate::generate_ir(ast, src, &mut issues))
+                .and_then(|module| evaluate(&module, src, &mut issues));
+        match value {
+            Some(value) => Ok(value.to_string()),
+            None => Err(issues.get_errors()[0].reason.clone())
+        }
+    }
+
+    #[test]
+    fn literals_evaluate_to_their_value() {
+        assert_eq!(run("fn main() : int32 { 42 }"), Ok("42".to_string()));
+        assert_eq!(run("fn main() : uint8 { 255 }"), Ok("255".to_string()));
+    }
+
+    #[test]
+    fn arithmetic_overflow_is_reported() {
+        let err = run("fn main() : int8 { let x = 127 : int8; x + 1 }").unwrap_err();
+        assert!(err.starts_with("integer overflow"), "{}", err);
+        let err = run("fn main() : uint32 { let x = 0 : uint32; x - 1 }").unwrap_err();
+        assert!(err.starts_with("integer overflow"), "{}", err);
+        assert_eq!(run("fn main() : int8 { let x = 126 : int8; x + 1 }"), Ok("127".to_string()));
+    }
+
+    #[test]
+    fn division_by_zero_is_reported() {
+        let reason = Err("attempted to divide by zero".to_string());
+        assert_eq!(run("fn main() : int32 { let x = 0; 1 / x }"), reason);
+        assert_eq!(run("fn main() : int32 { let x = 0; 1 % x }"), reason);
+    }
+
+    #[test]
+    fn remainder_overflows_with_its_quotient() {
+        let err = run("fn main() : int8 { let x : int8 = -128; let y : int8 = -1; x % y }").unwrap_err();
+        assert!(err.starts_with("integer overflow"), "{}", err);
+        assert_eq!(run("fn main() : int8 { let x : int8 = -128; let y : int8 = 3; x % y }"), Ok("-2".to_string()));
+    }
+
+    #[test]
+    fn shifts_by_the_width_of_the_type_are_reported() {
+        let err = run("fn main() : int32 { let x = 1; x << 32 }").unwrap_err();
+        assert!(err.starts_with("attempted to shift"), "{}", err);
+    }
+
+    #[test]
+    fn dereferenced_pointers_read_their_value() {
+        assert_eq!(run("fn deref(p : ^int32) : int32 { p^ }\n\
+                fn main() : int32 { let x = 7; deref(^x) }"), Ok("7".to_string()));
+        assert_eq!(run("fn main() : int32 { (^(2 + 3))^ }"), Ok("5".to_string()));
+    }
+
+    #[test]
+    fn left_shifts_wrap() {
+        assert_eq!(run("fn main() : uint8 { let x : uint8 = 200; x << 1 }"), Ok("144".to_string()));
+        assert_eq!(run("fn main() : int8 { let x : int8 = 100; x << 1 }"), Ok("-56".to_string()));
+    }
+
+    #[test]
+    fn pointers_to_immutable_variables_point_to_a_copy() {
+        assert_eq!(run("fn main() : int32 { let x = 41; let p = ^x; p^ = 5; x * 10 + p^ }"), Ok("415".to_string()));
+        assert_eq!(run("fn main() : int32 { let mut x = 41; let p = ^x; p^ = 5; x }"), Ok("5".to_string()));
+    }
+
+    #[test]
+    fn float_to_int_casts_saturate() {
+        assert_eq!(run("fn main() : uint8 { let x = 300.7; x as uint8 }"), Ok("255".to_string()));
+        assert_eq!(run("fn main() : int8 { let x = -129.9; x as int8 }"), Ok("-128".to_string()));
+        assert_eq!(run("fn main() : int32 { let x = -3.9; x as int32 }"), Ok("-3".to_string()));
+        assert_eq!(run("fn main() : uint8 { let x = 1e9; let y : uint8 = to_int(x); y }"), Ok("255".to_string()));
+    }
+
+    #[test]
+    fn nan_is_cast_to_zero() {
+        assert_eq!(run("fn main() : int32 { let z = 0.0; (z / z) as int32 }"), Ok("0".to_string()));
+    }
+}
@@ -4,29 +4,131 @@ use libcosyc_diagnostic::source::Span;
 /// Represents the possible types of instructions.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum TypeKind {
-    /// A type that has not been evaluated yet.
-    Variable,
+    /// A type with this name, which has not been evaluated yet.
+    Variable {
+        name : Span
+    },
     /// A type that should be inferred by the compiler.
     Infer,
+    /// A type variable with this id, which is solved by unification during type checking.
+    /// The types which can solve it are restricted by its class.
+    Unknown {
+        id : usize,
+        class : TypeClass
+    },
     /// The type of non-terminating programs.
     Void,
     /// The type of statements and boring functions.
     Empty,
+    /// The type of `true` and `false`.
+    Bool,
     /// Signed integers.
     Int(u8),
     /// Unsigned integers.
     UInt(u8),
+    /// IEEE-754 floating point numbers.
+    Float(u8),
+    /// A unicode scalar value, stored as a 32-bit unsigned integer.
+    Char,
+    /// A string of bytes, stored as a pointer to its first byte and its length.
+    Str,
+    /// The struct type defined by the item with this id.
+    Struct {
+        id : usize,
+        name : String
+    },
+    /// A pointer to a value of this type.
+    Pointer(Box<TypeKind>),
+    /// A tuple of values of these types.
+    Tuple(Vec<TypeKind>),
+    /// A fixed-length array of values of this type.
+    Array {
+        element : Box<TypeKind>,
+        length : u64
+    },
+    /// A function with these parameter types and return type.
+    Function {
+        params : Vec<TypeKind>,
+        ret : Box<TypeKind>
+    }
 }
 
 impl fmt::Display for TypeKind {
     fn fmt(&self, out : &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Variable => write!(out, "<variable>"),
+            Self::Variable { .. } => write!(out, "<variable>"),
             Self::Infer => write!(out, "<infer>"),
+            Self::Unknown { class : TypeClass::Any, .. } => write!(out, "_"),
+            Self::Unknown { class : TypeClass::Numeric, .. } => write!(out, "{{number}}"),
+            Self::Unknown { class : TypeClass::Integral, .. } => write!(out, "{{integer}}"),
+            Self::Unknown { class : TypeClass::Float, .. } => write!(out, "{{float}}"),
             Self::Void => write!(out, "void"),
-            Self::Empty => write!(out, "empty"),
+            Self::Empty => write!(out, "()"),
+            Self::Bool => write!(out, "bool"),
             Self::Int(n) => write!(out, "int{}", n),
             Self::UInt(n) => write!(out, "uint{}", n),
+            Self::Float(n) => write!(out, "float{}", n),
+            Self::Char => write!(out, "char"),
+            Self::Str => write!(out, "str"),
+            Self::Struct { name, .. } => write!(out, "{}", name),
+            Self::Pointer(ty) => write!(out, "^{}", ty),
+            Self::Tuple(elements) => {
+                let elements = elements.iter()
+                        .map(|ty| ty.to_string())
+                        .collect::<Vec<_>>();
+                write!(out, "({})", elements.join(", "))
+            },
+            Self::Array { element, length } => write!(out, "[{}; {}]", element, length),
+            Self::Function { params, ret } => match params.as_slice() {
+                [param] if !matches!(param, Self::Tuple(_) | Self::Function { .. }) => {
+                    write!(out, "{} -> {}", param, ret)
+                },
+                _ => {
+                    let params = params.iter()
+                            .map(|ty| ty.to_string())
+                            .collect::<Vec<_>>();
+                    write!(out, "({}) -> {}", params.join(", "), ret)
+                }
+            }
+        }
+    }
+}
+
+/// Represents a restriction on the types which can solve a type variable.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TypeClass {
+    /// Any type.
+    Any,
+    /// Integer or floating point types.
+    Numeric,
+    /// Integer types.
+    Integral,
+    /// Floating point types.
+    Float
+}
+
+impl TypeClass {
+    /// Returns whether this type is a member of this class. Type variables are members if
+    /// their class is at least as restrictive.
+    pub fn admits(&self, ty : &TypeKind) -> bool {
+        match (self, ty) {
+            (Self::Any, _) => true,
+            (_, TypeKind::Unknown { class, .. }) => self.meet(*class) == Some(*class),
+            (Self::Numeric, TypeKind::Int(_) | TypeKind::UInt(_) | TypeKind::Float(_)) => true,
+            (Self::Integral, TypeKind::Int(_) | TypeKind::UInt(_)) => true,
+            (Self::Float, TypeKind::Float(_)) => true,
+            _ => false
+        }
+    }
+
+    /// Returns the most general class whose members are in both of these classes, if
+    /// one exists.
+    pub fn meet(self, other : Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Any, x) | (x, Self::Any) => Some(x),
+            (Self::Numeric, x) | (x, Self::Numeric) => Some(x),
+            (x, y) if x == y => Some(x),
+            _ => None
         }
     }
 }
@@ -36,6 +138,7 @@ impl TypeKind {
     pub fn from_name(str : &str) -> Option<Self> {
         let ty = match str {
             "void" => Self::Void,
+            "bool" => Self::Bool,
             "int8" => Self::Int(8),
             "int16" => Self::Int(16),
             "int32" => Self::Int(32),
@@ -44,10 +147,155 @@ impl TypeKind {
             "uint16" => Self::UInt(16),
             "uint32" => Self::UInt(32),
             "uint64" => Self::UInt(64),
+            "float32" => Self::Float(32),
+            "float64" => Self::Float(64),
+            "char" => Self::Char,
+            "str" => Self::Str,
             _ => return None
         };
         Some(ty)
     }
+
+    /// Returns the types this type is composed of, such as the type a pointer points to.
+    pub fn components(&self) -> Vec<&Self> {
+        match self {
+            Self::Pointer(ty) | Self::Array { element : ty, .. } => vec![ty],
+            Self::Tuple(elements) => elements.iter().collect(),
+            Self::Function { params, ret } => params.iter().chain(Some(ret.as_ref())).collect(),
+            _ => Vec::new()
+        }
+    }
+
+    /// Returns mutable references to the types this type is composed of.
+    pub fn components_mut(&mut self) -> Vec<&mut Self> {
+        match self {
+            Self::Pointer(ty) | Self::Array { element : ty, .. } => vec![ty],
+            Self::Tuple(elements) => elements.iter_mut().collect(),
+            Self::Function { params, ret } => params.iter_mut().chain(Some(ret.as_mut())).collect(),
+            _ => Vec::new()
+        }
+    }
+
+    /// Returns whether these two types are built using the same type constructor, and have
+    /// the same number of components.
+    pub fn same_constructor(&self, other : &Self) -> bool {
+        match (self, other) {
+            (Self::Pointer(_), Self::Pointer(_)) => true,
+            (Self::Tuple(a), Self::Tuple(b)) => a.len() == b.len(),
+            (Self::Array { length : a, .. }, Self::Array { length : b, .. }) => a == b,
+            (Self::Function { params : a, .. }, Self::Function { params : b, .. }) => a.len() == b.len(),
+            _ => false
+        }
+    }
+}
+
+/// Returns whether this name is an operator, such as `+` or `+>`, rather than an identifier.
+pub fn is_operator(name : &str) -> bool {
+    !name.starts_with(|c : char| c.is_alphanumeric() || c == '_')
+}
+
+/// Represents the built-in operators over primitive types. Integer operators abort the program
+/// if their result overflows, if they divide by zero, or if they shift by a negative amount or
+/// by at least the width of the type. Bits shifted past the width of the type are discarded.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BuiltinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    BitAnd,
+    BitOr,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Neg,
+    BitNot,
+    /// Writes a string to the standard output.
+    Print,
+    /// Converts a number into a floating point number of the expected type.
+    ToFloat,
+    /// Converts a floating point number into an integer of the expected type, rounding
+    /// towards zero. Values which do not fit into that type saturate at its bounds, and NaN
+    /// is converted into zero.
+    ToInt
+}
+
+impl fmt::Display for BuiltinOp {
+    fn fmt(&self, out : &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Add => "+",
+            Self::Sub | Self::Neg => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::BitAnd => "&",
+            Self::BitOr => "|",
+            Self::Shl => "<<",
+            Self::Shr => ">>",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::BitNot => "~",
+            Self::Print => "print",
+            Self::ToFloat => "to_float",
+            Self::ToInt => "to_int"
+        };
+        write!(out, "{}", name)
+    }
+}
+
+impl BuiltinOp {
+    /// Attempts to find a built-in operator with this name and number of arguments.
+    pub fn from_name(name : &str, arity : usize) -> Option<Self> {
+        let op = match (name, arity) {
+            ("+", 2) => Self::Add,
+            ("-", 2) => Self::Sub,
+            ("*", 2) => Self::Mul,
+            ("/", 2) => Self::Div,
+            ("%", 2) => Self::Rem,
+            ("&", 2) => Self::BitAnd,
+            ("|", 2) => Self::BitOr,
+            ("<<", 2) => Self::Shl,
+            (">>", 2) => Self::Shr,
+            ("==", 2) => Self::Eq,
+            ("!=", 2) => Self::Ne,
+            ("<", 2) => Self::Lt,
+            ("<=", 2) => Self::Le,
+            (">", 2) => Self::Gt,
+            (">=", 2) => Self::Ge,
+            ("-", 1) => Self::Neg,
+            ("~", 1) => Self::BitNot,
+            ("print", 1) => Self::Print,
+            ("to_float", 1) => Self::ToFloat,
+            ("to_int", 1) => Self::ToInt,
+            _ => return None
+        };
+        Some(op)
+    }
+
+    /// Returns whether this operator compares its arguments.
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge)
+    }
+
+    /// Returns whether this operator can be applied to floating point numbers.
+    pub fn is_arithmetic(&self) -> bool {
+        self.is_comparison() || matches!(self, Self::Add | Self::Sub | Self::Mul | Self::Div | Self::Neg)
+    }
+
+    /// Returns whether this operator can also be applied to booleans.
+    pub fn is_logical(&self) -> bool {
+        matches!(self, Self::Eq | Self::Ne | Self::BitAnd | Self::BitOr)
+    }
 }
 
 /// Represents a node for the type of an IR instruction.
@@ -67,13 +315,117 @@ impl InstType {
 /// Represents a kind of expression.
 #[derive(Debug)]
 pub enum InstKind {
+    /// A variable which has not been resolved yet.
     Variable,
+    /// A reference to the local variable with this id.
+    Local {
+        id : usize
+    },
+    /// A reference to the top-level item with this id.
+    Global {
+        id : usize
+    },
+    /// An item associated with this type, which is resolved during type checking.
+    Associated {
+        datatype : InstType,
+        name : Span
+    },
+    /// The callsite of a method call. The method is an item associated with the type of
+    /// the first argument, or the type it points to, and is resolved during type checking.
+    Method {
+        name : Span
+    },
+    /// A reference to a built-in operator.
+    Builtin {
+        op : BuiltinOp
+    },
+    /// An integer literal. Its value is checked to fit its type during type checking, after
+    /// negated literals are folded into a single negative literal.
     Integral {
-        radix : u8
+        value : i128
+    },
+    /// A boolean literal.
+    Boolean {
+        value : bool
+    },
+    /// A character literal.
+    Char {
+        value : char
+    },
+    /// A floating point literal.
+    Float {
+        value : f64
+    },
+    /// A string literal, with its escape sequences replaced by the bytes they represent.
+    String {
+        value : Vec<u8>
     },
     FunctionApp {
         callsite : Box<Inst>,
         args : Vec<Inst>
+    },
+    /// Evaluates each statement in order, followed by the value of the block. Blocks without
+    /// a value have the type `()`.
+    Block {
+        stmts : Vec<Inst>,
+        value : Option<Box<Inst>>
+    },
+    /// Evaluates one of two branches depending on a boolean condition. A branch of type
+    /// `void` takes the type of the other branch.
+    If {
+        condition : Box<Inst>,
+        on_true : Box<Inst>,
+        on_false : Box<Inst>
+    },
+    /// Stores a value in a mutable variable, a field of a mutable variable, or behind a pointer.
+    Assign {
+        target : Box<Inst>,
+        value : Box<Inst>
+    },
+    /// Evaluates its body repeatedly, until it is exited using `break`.
+    Loop {
+        body : Box<Inst>
+    },
+    /// Exits the innermost loop.
+    Break,
+    /// Skips to the next iteration of the innermost loop.
+    Continue,
+    /// Binds a value to the local variable with this id, for the scope of the body.
+    Let {
+        id : usize,
+        name : Span,
+        mutable : bool,
+        value : Box<Inst>,
+        body : Box<Inst>
+    },
+    /// A struct literal. Its fields are evaluated in the order they are written, and the index
+    /// of each field in the declaration order of the struct is assigned during type checking.
+    Struct {
+        fields : Vec<(Span, usize, Inst)>
+    },
+    /// Takes the address of a value. The address of a mutable local variable points to the
+    /// variable itself, and other values, including immutable variables, are copied into a new
+    /// temporary.
+    Reference {
+        value : Box<Inst>
+    },
+    /// Reads the value behind a pointer.
+    Dereference {
+        value : Box<Inst>
+    },
+    /// Converts a value into the type `target`. Integers are truncated, sign-extended or
+    /// zero-extended depending on their signedness. Floating point numbers are rounded towards
+    /// zero when they are converted into integers, saturating at the bounds of the integer type,
+    /// and NaN is converted into zero.
+    Cast {
+        value : Box<Inst>,
+        target : InstType
+    },
+    /// Accesses a field of a struct. The index of the field is assigned during type checking.
+    Field {
+        value : Box<Inst>,
+        name : Span,
+        index : usize
     }
 }
 
@@ -97,3 +449,105 @@ impl Inst {
         Self::new_typed(span, kind, datatype)
     }
 }
+
+/// Represents a parameter of a function, bound to the local variable with this id.
+#[derive(Debug)]
+pub struct Param {
+    pub id : usize,
+    pub name : Span,
+    pub datatype : InstType
+}
+
+/// Represents a named field of a struct type.
+#[derive(Debug)]
+pub struct Field {
+    pub name : Span,
+    pub datatype : InstType
+}
+
+/// Represents a kind of top-level item.
+#[derive(Debug)]
+pub enum ItemKind {
+    /// A function whose body is evaluated each time it is called.
+    Function {
+        params : Vec<Param>,
+        return_type : InstType,
+        body : Inst
+    },
+    /// A constant whose value is evaluated each time it is referenced.
+    Definition {
+        value : Inst
+    },
+    /// A type alias.
+    Data {
+        datatype : InstType
+    },
+    /// A struct type with these fields.
+    Struct {
+        fields : Vec<Field>
+    }
+}
+
+/// Represents a node for a top-level item. Items defined inside an impl block are owned
+/// by the type of that block.
+#[derive(Debug)]
+pub struct Item {
+    pub span : Span,
+    pub name : Span,
+    pub owner : Option<InstType>,
+    pub kind : ItemKind
+}
+
+impl Item {
+    /// Returns the name of this item, qualified by the name of its owner as `T::name`.
+    pub fn qualified_name(&self, src : &str) -> String {
+        let name = self.name.render(src);
+        if let Some(owner) = &self.owner {
+            format!("{}::{}", owner.span.render(src), name)
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+/// Represents the items of a program. Items are referred to by their index.
+#[derive(Debug)]
+pub struct Module {
+    pub items : Vec<Item>
+}
+
+impl Module {
+    /// Looks up the id of the item with this name, ignoring items owned by a type.
+    pub fn find_item(&self, name : &str, src : &str) -> Option<usize> {
+        self.items.iter()
+                .position(|item| item.owner.is_none() && item.name.render(src) == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meet_is_the_more_restrictive_class() {
+        use TypeClass::*;
+        assert_eq!(Any.meet(Float), Some(Float));
+        assert_eq!(Numeric.meet(Integral), Some(Integral));
+        assert_eq!(Integral.meet(Numeric), Some(Integral));
+        assert_eq!(Float.meet(Float), Some(Float));
+        assert_eq!(Integral.meet(Float), None);
+        assert_eq!(Float.meet(Integral), None);
+    }
+
+    #[test]
+    fn classes_admit_their_members() {
+        use TypeClass::*;
+        assert!(Numeric.admits(&TypeKind::UInt(8)));
+        assert!(Numeric.admits(&TypeKind::Float(32)));
+        assert!(!Integral.admits(&TypeKind::Float(64)));
+        assert!(!Float.admits(&TypeKind::Bool));
+        assert!(Any.admits(&TypeKind::Str));
+        assert!(Numeric.admits(&TypeKind::Unknown { id : 0, class : Integral }));
+        assert!(!Integral.admits(&TypeKind::Unknown { id : 0, class : Numeric }));
+    }
+}
@@ -1,12 +1,15 @@
 pub mod ir;
 pub mod desugar;
+pub mod resolve;
 pub mod typecheck;
+pub mod eval;
 
 use libcosyc_diagnostic::error::IssueTracker;
 use libcosyc_parse::syntax as ast;
 
 /// Applies semantic analysis to this AST and returns valid IR.
-pub fn generate_ir(ast : ast::Term, src : &str, issues : &mut IssueTracker) -> Option<ir::Inst> {
-    let inst = desugar::surface_into_core(ast, src, issues)?;
-    typecheck::check(inst, src, issues)
+pub fn generate_ir(ast : ast::Module, src : &str, issues : &mut IssueTracker) -> Option<ir::Module> {
+    let mut module = desugar::surface_into_core(ast, src, issues)?;
+    resolve::resolve(&mut module, src, issues)?;
+    typecheck::check(module, src, issues)
 }
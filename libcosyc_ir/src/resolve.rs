@@ -0,0 +1,206 @@
+use crate::ir;
+use libcosyc_diagnostic::{
+    error::{ CompilerError, IssueTracker, Failable },
+    source::Renderable
+};
+use std::collections::HashMap;
+
+/// Manages the resolution of variable names to their bindings.
+pub struct NameResolver<'a> {
+    src : &'a str,
+    issues : &'a mut IssueTracker,
+    scope : Vec<(&'a str, usize)>,
+    items : HashMap<&'a str, usize>
+}
+
+impl Failable for NameResolver<'_> {
+    fn issues(&mut self) -> &mut IssueTracker {
+        self.issues
+    }
+}
+
+impl Renderable for NameResolver<'_> {
+    fn src(&self) -> &str {
+        self.src
+    }
+}
+
+impl<'a> NameResolver<'a> {
+    /// Creates a new instance from this issue tracker and source file.
+    pub fn new(src : &'a str, issues : &'a mut IssueTracker) -> Self {
+        let scope = Vec::new();
+        let items = HashMap::new();
+        Self { src, issues, scope, items }
+    }
+
+    /// Looks up the id of the innermost local variable with this name.
+    pub fn find_local(&self, name : &str) -> Option<usize> {
+        self.scope.iter()
+                .rev()
+                .find(|(local, _)| *local == name)
+                .map(|(_, id)| *id)
+    }
+
+    /// Registers the names of these items, and reports an error if any name is defined twice
+    /// or redefines a built-in operator. Items owned by a type are not registered, since they
+    /// are resolved during type checking.
+    pub fn declare_items(&mut self, items : &[ir::Item]) -> Option<()> {
+        let src = self.src;
+        for (id, item) in items.iter().enumerate() {
+            if item.owner.is_some() {
+                continue;
+            }
+            let name = item.name.render(src);
+            if ir::is_operator(name) && (ir::BuiltinOp::from_name(name, 1).is_some()
+                    || ir::BuiltinOp::from_name(name, 2).is_some()) {
+                self.report(CompilerError::new()
+                        .reason(format!("the built-in operator `{}` cannot be redefined", name))
+                        .span(&item.name)
+                        .note("consider giving this operator a different name"))?;
+            }
+            if self.items.insert(name, id).is_some() {
+                self.report(CompilerError::new()
+                        .reason(format!("an item with the name `{}` is already defined", name))
+                        .span(&item.name)
+                        .note("consider renaming this item"))?;
+            }
+        }
+        Some(())
+    }
+
+    /// Links every variable in this module to its binding.
+    pub fn resolve_module(&mut self, module : &mut ir::Module) -> Option<()> {
+        self.declare_items(&module.items)?;
+        let src = self.src;
+        for item in &mut module.items {
+            match &mut item.kind {
+                ir::ItemKind::Function { params, body, .. } => {
+                    for param in params.iter() {
+                        self.scope.push((param.name.render(src), param.id));
+                    }
+                    let result = self.resolve(body);
+                    self.scope.clear();
+                    result?;
+                },
+                ir::ItemKind::Definition { value } => self.resolve(value)?,
+                ir::ItemKind::Data { .. } | ir::ItemKind::Struct { .. } => ()
+            }
+        }
+        Some(())
+    }
+
+    /// Links the callsite of a function application to its binding. Callsites which are not
+    /// local variables or items are resolved to built-in operators, so operators defined by
+    /// the user are found before built-in ones.
+    pub fn resolve_callsite(&mut self, callsite : &mut ir::Inst, arity : usize) -> Option<()> {
+        if !matches!(callsite.kind, ir::InstKind::Variable) {
+            return self.resolve(callsite);
+        }
+        let src = self.src;
+        let name = callsite.span.render(src);
+        if self.find_local(name).is_some() || self.items.contains_key(name) {
+            self.resolve(callsite)
+        } else if let Some(op) = ir::BuiltinOp::from_name(name, arity) {
+            callsite.kind = ir::InstKind::Builtin { op };
+            Some(())
+        } else if !ir::is_operator(name) {
+            let reason = format!("a function with the name `{}` does not exist in the current context", name);
+            self.report(CompilerError::new()
+                    .reason(reason)
+                    .span(&callsite.span))
+        } else {
+            let reason = format!("an operator with the name `{}` taking {} argument(s) does not exist in the current context", name, arity);
+            let params = ["a : T", "b : T"][..arity.min(2)].join(", ");
+            self.report(CompilerError::new()
+                    .reason(reason)
+                    .span(&callsite.span)
+                    .note(format!("operators are defined as functions with a raw identifier, such as fn `{}`({}) : T", name, params)))
+        }
+    }
+
+    /// Links every variable in this instruction to its binding. Reports an error if a
+    /// variable does not exist in the current scope.
+    pub fn resolve(&mut self, inst : &mut ir::Inst) -> Option<()> {
+        match &mut inst.kind {
+            ir::InstKind::Variable => {
+                let src = self.src;
+                let name = inst.span.render(src);
+                if let Some(id) = self.find_local(name) {
+                    inst.kind = ir::InstKind::Local { id };
+                } else if let Some(id) = self.items.get(name) {
+                    inst.kind = ir::InstKind::Global { id : *id };
+                } else {
+                    let reason = format!("a variable with the name `{}` does not exist in the current context", name);
+                    self.report(CompilerError::new()
+                            .reason(reason)
+                            .span(&inst.span))?;
+                }
+            },
+            ir::InstKind::Local { .. }
+                | ir::InstKind::Global { .. }
+                | ir::InstKind::Associated { .. }
+                | ir::InstKind::Method { .. }
+                | ir::InstKind::Builtin { .. }
+                | ir::InstKind::Integral { .. }
+                | ir::InstKind::Float { .. }
+                | ir::InstKind::Boolean { .. }
+                | ir::InstKind::Char { .. }
+                | ir::InstKind::String { .. }
+                | ir::InstKind::Break
+                | ir::InstKind::Continue => (),
+            ir::InstKind::FunctionApp { callsite, args } => {
+                self.resolve_callsite(callsite, args.len())?;
+                for arg in args {
+                    self.resolve(arg)?;
+                }
+            },
+            ir::InstKind::Struct { fields } => {
+                for (_, _, value) in fields {
+                    self.resolve(value)?;
+                }
+            },
+            ir::InstKind::Field { value, .. }
+                | ir::InstKind::Reference { value }
+                | ir::InstKind::Dereference { value }
+                | ir::InstKind::Cast { value, .. } => self.resolve(value)?,
+            ir::InstKind::Block { stmts, value } => {
+                for stmt in stmts {
+                    self.resolve(stmt)?;
+                }
+                if let Some(value) = value {
+                    self.resolve(value)?;
+                }
+            },
+            ir::InstKind::If { condition, on_true, on_false } => {
+                self.resolve(condition)?;
+                self.resolve(on_true)?;
+                self.resolve(on_false)?;
+            },
+            ir::InstKind::Assign { target, value } => {
+                self.resolve(target)?;
+                self.resolve(value)?;
+            },
+            ir::InstKind::Loop { body } => self.resolve(body)?,
+            ir::InstKind::Let { id, name, value, body, .. } => {
+                self.resolve(value)?;
+                let src = self.src;
+                let name = name.render(src);
+                let bound = name != "_";
+                if bound {
+                    self.scope.push((name, *id));
+                }
+                let result = self.resolve(body);
+                if bound {
+                    self.scope.pop();
+                }
+                result?;
+            }
+        }
+        Some(())
+    }
+}
+
+/// Resolves the variables of this IR.
+pub fn resolve(module : &mut ir::Module, src : &str, issues : &mut IssueTracker) -> Option<()> {
+    NameResolver::new(src, issues).resolve_module(module)
+}
@@ -1,8 +1,9 @@
-use crate::ir;
+use crate::{ ir, eval::int_range };
 use libcosyc_diagnostic::{
     error::{ CompilerError, IssueTracker, Failable },
     source::{ Span, Renderable }
 };
+use std::collections::{ HashMap, HashSet };
 
 macro_rules! int_types {
     () => {{
@@ -20,10 +21,121 @@ macro_rules! int_types {
     }}
 }
 
-/// Manages the validation of IR.
+macro_rules! float_types {
+    () => {{
+        use ir::TypeKind as TK;
+        &[
+            TK::Float(32),
+            TK::Float(64),
+        ]
+    }}
+}
+
+/// Represents the type signature of a function.
+#[derive(Debug, Clone)]
+pub struct Signature {
+    pub params : Vec<ir::TypeKind>,
+    pub ret : ir::TypeKind
+}
+
+/// Returns the signature of a built-in operator whose operands have this type.
+pub fn builtin_signature(op : ir::BuiltinOp, operand : &ir::TypeKind) -> Signature {
+    use ir::BuiltinOp as Op;
+    let (arity, ret) = match op {
+        Op::Neg | Op::BitNot => (1, operand.clone()),
+        x if x.is_comparison() => (2, ir::TypeKind::Bool),
+        _ => (2, operand.clone())
+    };
+    let params = vec![operand.clone(); arity];
+    Signature { params, ret }
+}
+
+/// Returns whether this type contains any unsolved type variables.
+fn is_unknown(ty : &ir::TypeKind) -> bool {
+    match ty {
+        ir::TypeKind::Unknown { .. } => true,
+        _ => ty.components().into_iter().any(is_unknown)
+    }
+}
+
+/// Returns the value of this instruction if it is the negation of an integer literal token,
+/// such as `-128`. Negated groupings such as `-(128)` are not literals.
+fn negated_literal(inst : &ir::Inst, src : &str) -> Option<i128> {
+    if let ir::InstKind::FunctionApp { callsite, args } = &inst.kind {
+        if let (ir::InstKind::Builtin { op : ir::BuiltinOp::Neg }, [arg]) = (&callsite.kind, args.as_slice()) {
+            let between = src.get(callsite.span.end..arg.span.begin);
+            let bare = matches!(between, Some(between) if between.trim().is_empty());
+            match arg.kind {
+                ir::InstKind::Integral { value } if bare => return Some(-value),
+                _ => ()
+            }
+        }
+    }
+    None
+}
+
+/// Returns whether a value of type `from` can be cast to the type `to`. Numbers can be cast to
+/// any numeric type, booleans and characters can be cast to integers, and `uint8` values can be
+/// cast to characters.
+fn is_valid_cast(from : &ir::TypeKind, to : &ir::TypeKind) -> bool {
+    let numeric = ir::TypeClass::Numeric;
+    match (from, to) {
+        _ if from == to => true,
+        (ir::TypeKind::UInt(8), ir::TypeKind::Char) => true,
+        (ir::TypeKind::Bool, to) | (ir::TypeKind::Char, to) => ir::TypeClass::Integral.admits(to),
+        _ => numeric.admits(from) && numeric.admits(to)
+    }
+}
+
+/// Returns whether converting a value of type `from` into the type `to` can lose information.
+fn is_narrowing(from : &ir::TypeKind, to : &ir::TypeKind) -> bool {
+    use ir::TypeKind as TK;
+    match (from, to) {
+        (TK::Float(_), TK::Int(_) | TK::UInt(_)) => true,
+        (TK::Float(a), TK::Float(b)) => b < a,
+        (TK::Int(n) | TK::UInt(n), TK::Float(m)) => {
+            let mantissa = if *m == 32 { 24 } else { 53 };
+            *n > mantissa
+        },
+        (TK::Char, to) => !matches!(int_range(to), Some((_, max)) if max >= char::MAX as i128),
+        _ => match (int_range(from), int_range(to)) {
+            (Some((from_min, from_max)), Some((to_min, to_max))) => from_min < to_min || from_max > to_max,
+            _ => false
+        }
+    }
+}
+
+/// Adds notes to this type error suggesting an explicit cast, if a value of type `from` can be
+/// cast to the type `to`.
+fn suggest_cast(err : CompilerError, from : &ir::TypeKind, to : &ir::TypeKind) -> CompilerError {
+    if is_unknown(from) || is_unknown(to) || from == to || !is_valid_cast(from, to) {
+        return err;
+    }
+    let err = err.note(format!("consider converting the value using `as {}`", to));
+    if is_narrowing(from, to) {
+        err.note(format!("converting `{}` to `{}` may lose information, since not every value of type `{}` can be represented", from, to, from))
+    } else {
+        err
+    }
+}
+
+/// Manages the validation of IR. The types of unannotated values are inferred by unifying
+/// type variables, whose solutions are stored in `vars`. Type aliases are stored in `aliases`
+/// until they are first used.
 pub struct TypeChecker<'a> {
     src : &'a str,
-    issues : &'a mut IssueTracker
+    issues : &'a mut IssueTracker,
+    locals : HashMap<usize, ir::TypeKind>,
+    mutable : HashSet<usize>,
+    loops : usize,
+    vars : Vec<Option<ir::TypeKind>>,
+    globals : HashMap<usize, ir::TypeKind>,
+    functions : HashMap<usize, Signature>,
+    types : HashMap<&'a str, ir::TypeKind>,
+    aliases : HashMap<&'a str, ir::TypeKind>,
+    resolving : Vec<&'a str>,
+    structs : HashMap<usize, Vec<(&'a str, ir::TypeKind)>>,
+    associated : HashMap<usize, Vec<(&'a str, usize)>>
 }
 
 impl Failable for TypeChecker<'_> {
@@ -41,18 +153,107 @@ impl Renderable for TypeChecker<'_> {
 impl<'a> TypeChecker<'a> {
     /// Creates a new instance from this issue tracker and source file.
     pub fn new(src : &'a str, issues : &'a mut IssueTracker) -> Self {
-        Self { src, issues }
+        let locals = HashMap::new();
+        let mutable = HashSet::new();
+        let loops = 0;
+        let vars = Vec::new();
+        let globals = HashMap::new();
+        let functions = HashMap::new();
+        let types = HashMap::new();
+        let aliases = HashMap::new();
+        let resolving = Vec::new();
+        let structs = HashMap::new();
+        let associated = HashMap::new();
+        Self { src, issues, locals, mutable, loops, vars, globals, functions, types, aliases, resolving,
+                structs, associated }
     }
 
-    /// Asserts whether this instruction has one of the following types.
-    pub fn expect_type(&mut self, inst : &ir::Inst, expect : &[ir::TypeKind]) -> Option<()> {
-        let span = &inst.span;
-        let datatype = &inst.datatype;
-        for ty_kind in expect {
-            if datatype.kind == *ty_kind {
-                return Some(());
+    /// Creates a new unsolved type variable, which can only be solved by members of this class.
+    pub fn fresh_var(&mut self, class : ir::TypeClass) -> ir::TypeKind {
+        let id = self.vars.len();
+        self.vars.push(None);
+        ir::TypeKind::Unknown { id, class }
+    }
+
+    /// Replaces any solved type variables in this type with their solutions.
+    pub fn prune(&self, ty : &ir::TypeKind) -> ir::TypeKind {
+        if let ir::TypeKind::Unknown { id, .. } = ty {
+            if let Some(solution) = &self.vars[*id] {
+                return self.prune(solution);
             }
         }
+        let mut ty = ty.clone();
+        for component in ty.components_mut() {
+            *component = self.prune(component);
+        }
+        ty
+    }
+
+    /// Returns whether the type variable with this id occurs inside of this type.
+    fn occurs(&self, id : usize, ty : &ir::TypeKind) -> bool {
+        match self.prune(ty) {
+            ir::TypeKind::Unknown { id : other, .. } => id == other,
+            ty => ty.components().into_iter().any(|ty| self.occurs(id, ty))
+        }
+    }
+
+    /// Attempts to make these two types equal by solving type variables, and returns whether
+    /// this was successful.
+    pub fn unify(&mut self, a : &ir::TypeKind, b : &ir::TypeKind) -> bool {
+        let a = self.prune(a);
+        let b = self.prune(b);
+        match (&a, &b) {
+            _ if a == b => true,
+            (ir::TypeKind::Unknown { id, class }, other @ ir::TypeKind::Unknown { .. })
+                    | (other @ ir::TypeKind::Unknown { .. }, ir::TypeKind::Unknown { id, class })
+                    if class.admits(other) => {
+                // the variable which remains unsolved has the more restrictive class
+                self.vars[*id] = Some(other.clone());
+                true
+            },
+            (ir::TypeKind::Unknown { id, class }, ty)
+                    | (ty, ir::TypeKind::Unknown { id, class }) => {
+                if !class.admits(ty) || self.occurs(*id, ty) {
+                    false
+                } else {
+                    self.vars[*id] = Some(ty.clone());
+                    true
+                }
+            },
+            _ if a.same_constructor(&b) => a.components().into_iter()
+                    .zip(b.components())
+                    .all(|(a, b)| self.unify(a, b)),
+            _ => false
+        }
+    }
+
+    /// Asserts whether this instruction has this type, solving any type variables if necessary.
+    pub fn expect_type(&mut self, inst : &ir::Inst, expect : &ir::TypeKind) -> Option<()> {
+        if self.unify(expect, &inst.datatype.kind) {
+            return Some(());
+        }
+        let expect = self.prune(expect);
+        let datatype = self.prune(&inst.datatype.kind);
+        let mut err = CompilerError::new()
+                .span(&inst.span.join(&inst.datatype.span))
+                .reason(format!("expected a value of type `{}` (got `{}`)", expect, datatype));
+        if is_unknown(&expect) || is_unknown(&datatype) {
+            err = err.note("consider adding a type annotation");
+        }
+        self.report(suggest_cast(err, &datatype, &expect))
+    }
+
+    /// Asserts whether the type of this instruction is a member of this class.
+    pub fn expect_class(&mut self, inst : &ir::Inst, class : ir::TypeClass) -> Option<()> {
+        let var = self.fresh_var(class);
+        if self.unify(&var, &inst.datatype.kind) {
+            return Some(());
+        }
+        let expect = match class {
+            ir::TypeClass::Integral => int_types!().to_vec(),
+            ir::TypeClass::Float => float_types!().to_vec(),
+            _ => [&int_types!()[..], &float_types!()[..]].concat()
+        };
         let mut types = String::new();
         let count = expect.len();
         for (i, ty_kind) in expect.iter().enumerate() {
@@ -63,42 +264,56 @@ impl<'a> TypeChecker<'a> {
             types.push_str(&ty_kind.to_string());
             types.push_str("`");
         }
+        let datatype = self.prune(&inst.datatype.kind);
         let mut err = CompilerError::new()
-                .span(&span.join(&datatype.span))
-                .reason(format!("expected a value of type{} (got `{}`)", types, datatype.kind));
-        if matches!(datatype.kind, ir::TypeKind::Infer) {
+                .span(&inst.span.join(&inst.datatype.span))
+                .reason(format!("expected a value of type{} (got `{}`)", types, datatype));
+        if is_unknown(&datatype) {
             err = err.note("consider adding a type annotation");
         }
         self.report(err)
     }
 
-    /// Asserts whether these two instructions have equivalent types.
+    /// Asserts whether these two instructions have equivalent types, solving any type variables
+    /// if necessary.
     pub fn expect_equal_types(&mut self, a : &ir::Inst, b : &ir::Inst) -> Option<()> {
-        let mut ty_a = &a.datatype;
-        let mut ty_b = &b.datatype;
-        if ty_a.kind == ty_b.kind {
+        if self.unify(&a.datatype.kind, &b.datatype.kind) {
             return Some(());
         }
-        if matches!(ty_a.kind, ir::TypeKind::Infer) {
-            let tmp = ty_a;
-            ty_a = ty_b;
-            ty_b = tmp;
-        }
+        let ty_a = self.prune(&a.datatype.kind);
+        let ty_b = self.prune(&b.datatype.kind);
         let mut err = CompilerError::new()
-                .span(&b.span)
-                .reason(format!("expected a value of type `{}` (got `{}`)", ty_a.kind, ty_b.kind));
-        if matches!(ty_a.kind, ir::TypeKind::Infer) ||
-                matches!(ty_b.kind, ir::TypeKind::Infer) {
+                .span(&b.span.join(&b.datatype.span))
+                .reason(format!("expected a value of type `{}` (got `{}`)", ty_a, ty_b));
+        if is_unknown(&ty_a) || is_unknown(&ty_b) {
             err = err.note("consider adding a type annotation");
         }
-        self.report(err)
+        self.report(suggest_cast(err, &ty_b, &ty_a))
     }
 
     /// Looks up a type variable in the current context and throws an error is the type doesn't exist.
+    /// Type aliases are resolved the first time they are used, so they can refer to aliases which
+    /// are defined after them.
     pub fn find_type(&mut self, span : &Span) -> Option<ir::TypeKind> {
-        let name = self.render(span);
+        let name = span.render(self.src);
         if let Some(ty) = ir::TypeKind::from_name(name) {
             Some(ty)
+        } else if let Some(ty) = self.types.get(name) {
+            Some(ty.clone())
+        } else if let Some(alias) = self.aliases.get(name).cloned() {
+            if self.resolving.contains(&name) {
+                return self.report(CompilerError::new()
+                        .span(span)
+                        .reason(format!("the type alias `{}` is defined in terms of itself", name))
+                        .note("consider using a struct with a pointer field instead"));
+            }
+            let mut ty = alias;
+            self.resolving.push(name);
+            let resolved = self.resolve_type_kind(&mut ty);
+            self.resolving.pop();
+            resolved?;
+            self.types.insert(name, ty.clone());
+            Some(ty)
         } else {
             let reason = format!("a type with the name `{}` does not exist in the current context", name);
             self.report(CompilerError::new()
@@ -107,31 +322,1123 @@ impl<'a> TypeChecker<'a> {
         }
     }
 
+    /// Replaces any type variables in this type with their definitions.
+    pub fn resolve_type(&mut self, ty : &mut ir::InstType) -> Option<()> {
+        self.resolve_type_kind(&mut ty.kind)
+    }
+
+    fn resolve_type_kind(&mut self, ty : &mut ir::TypeKind) -> Option<()> {
+        if let ir::TypeKind::Variable { name } = ty {
+            let name = name.clone();
+            *ty = self.find_type(&name)?;
+        } else {
+            for component in ty.components_mut() {
+                self.resolve_type_kind(component)?;
+            }
+        }
+        Some(())
+    }
+
+    /// Assigns a type to this instruction. If the instruction already has a type annotation,
+    /// then an error is reported if the two types cannot be unified.
+    pub fn assign_type(&mut self, inst : &mut ir::Inst, ty : ir::TypeKind) -> Option<()> {
+        let datatype = &mut inst.datatype;
+        if let ir::TypeKind::Infer = datatype.kind {
+            datatype.kind = ty;
+            Some(())
+        } else if self.unify(&inst.datatype.kind, &ty) {
+            Some(())
+        } else {
+            let expect = self.prune(&inst.datatype.kind);
+            let ty = self.prune(&ty);
+            let err = CompilerError::new()
+                    .span(&inst.span.join(&inst.datatype.span))
+                    .reason(format!("expected a value of type `{}` (got `{}`)", expect, ty));
+            self.report(suggest_cast(err, &ty, &expect))
+        }
+    }
+
+    /// Asserts that the value of this integer literal fits into its type.
+    pub fn expect_literal_range(&mut self, inst : &ir::Inst, value : i128) -> Option<()> {
+        let datatype = &inst.datatype;
+        let (min, max) = match int_range(&datatype.kind) {
+            Some(range) => range,
+            None => return Some(())
+        };
+        if (min..=max).contains(&value) {
+            return Some(());
+        }
+        let mut err = CompilerError::new()
+                .span(&inst.span.join(&datatype.span))
+                .reason(format!("the literal `{}` does not fit into the type `{}`", self.render(&inst.span), datatype.kind));
+        err = if value < min {
+            err.note(format!("the minimum value of `{}` is {}", datatype.kind, min))
+        } else {
+            err.note(format!("the maximum value of `{}` is {}", datatype.kind, max))
+        };
+        if let ir::TypeKind::Int(n) = datatype.kind {
+            if matches!(int_range(&ir::TypeKind::UInt(n)), Some((min, max)) if (min..=max).contains(&value)) {
+                err = err.note(format!("consider using the type `uint{}` instead", n));
+            }
+        }
+        self.report(err)
+    }
+
+    /// Asserts whether this floating point literal remains finite when it is rounded to its type.
+    pub fn expect_float_range(&mut self, inst : &ir::Inst, value : f64) -> Option<()> {
+        let datatype = &inst.datatype;
+        if datatype.kind != ir::TypeKind::Float(32) || (value as f32).is_finite() {
+            return Some(());
+        }
+        self.report(CompilerError::new()
+                .span(&inst.span.join(&datatype.span))
+                .reason(format!("the literal `{}` does not fit into the type `{}`", self.render(&inst.span), datatype.kind))
+                .note(format!("the maximum value of `{}` is {:e}", datatype.kind, f32::MAX))
+                .note("consider using the type `float64` instead"))
+    }
+
+    /// Replaces the type variables in the types of this instruction, and the instructions it
+    /// contains, with their solutions. Unsolved integral type variables default to `int32`, and
+    /// floating point type variables default to `float64`. Literals are checked to fit into their
+    /// inferred types.
+    pub fn substitute(&mut self, inst : &mut ir::Inst) -> Option<()> {
+        self.substitute_type(&mut inst.datatype)?;
+        match inst.kind {
+            ir::InstKind::Integral { value } => self.expect_literal_range(inst, value)?,
+            ir::InstKind::Float { value } => self.expect_float_range(inst, value)?,
+            _ => ()
+        }
+        match &mut inst.kind {
+            ir::InstKind::Variable
+                | ir::InstKind::Local { .. }
+                | ir::InstKind::Global { .. }
+                | ir::InstKind::Associated { .. }
+                | ir::InstKind::Method { .. }
+                | ir::InstKind::Builtin { .. }
+                | ir::InstKind::Integral { .. }
+                | ir::InstKind::Float { .. }
+                | ir::InstKind::Boolean { .. }
+                | ir::InstKind::Char { .. }
+                | ir::InstKind::String { .. }
+                | ir::InstKind::Break
+                | ir::InstKind::Continue => (),
+            ir::InstKind::FunctionApp { callsite, args } => {
+                self.substitute(callsite)?;
+                for arg in args {
+                    self.substitute(arg)?;
+                }
+            },
+            ir::InstKind::Block { stmts, value } => {
+                for stmt in stmts {
+                    self.substitute(stmt)?;
+                }
+                if let Some(value) = value {
+                    self.substitute(value)?;
+                }
+            },
+            ir::InstKind::Struct { fields } => {
+                for (_, _, value) in fields {
+                    self.substitute(value)?;
+                }
+            },
+            ir::InstKind::If { condition, on_true, on_false } => {
+                self.substitute(condition)?;
+                self.substitute(on_true)?;
+                self.substitute(on_false)?;
+            },
+            ir::InstKind::Assign { target : a, value : b }
+                | ir::InstKind::Let { value : a, body : b, .. } => {
+                self.substitute(a)?;
+                self.substitute(b)?;
+            },
+            ir::InstKind::Loop { body : value }
+                | ir::InstKind::Reference { value }
+                | ir::InstKind::Dereference { value }
+                | ir::InstKind::Cast { value, .. }
+                | ir::InstKind::Field { value, .. } => self.substitute(value)?
+        }
+        Some(())
+    }
+
+    fn substitute_type(&mut self, ty : &mut ir::InstType) -> Option<()> {
+        let mut kind = self.prune(&ty.kind);
+        if !self.default_numeric(&mut kind) {
+            self.report(CompilerError::new()
+                    .span(&ty.span)
+                    .reason("unable to infer the type of this value")
+                    .note("consider adding a type annotation"))?;
+        }
+        ty.kind = kind;
+        Some(())
+    }
+
+    /// Solves the numeric type variables in this type as `int32`, or `float64` if they must be
+    /// floating point numbers, and returns whether there are no other unsolved type variables.
+    fn default_numeric(&mut self, ty : &mut ir::TypeKind) -> bool {
+        let default = match ty {
+            ir::TypeKind::Unknown { class : ir::TypeClass::Any, .. } => return false,
+            ir::TypeKind::Unknown { class : ir::TypeClass::Float, .. } => ir::TypeKind::Float(64),
+            ir::TypeKind::Unknown { .. } => ir::TypeKind::Int(32),
+            _ => return ty.components_mut().into_iter().all(|ty| self.default_numeric(ty))
+        };
+        if let ir::TypeKind::Unknown { id, .. } = ty {
+            self.vars[*id] = Some(default.clone());
+        }
+        *ty = default;
+        true
+    }
+
+    /// Asserts that this instruction can be assigned to. Only mutable variables, fields of
+    /// values which can be assigned to, and dereferenced pointers can be assigned to.
+    pub fn expect_assignable(&mut self, inst : &ir::Inst) -> Option<()> {
+        match &inst.kind {
+            ir::InstKind::Local { id } if self.mutable.contains(id) => Some(()),
+            ir::InstKind::Local { .. } => {
+                let name = self.render(&inst.span);
+                self.report(CompilerError::new()
+                        .span(&inst.span)
+                        .reason(format!("cannot assign to the immutable variable `{}`", name))
+                        .note(format!("consider declaring it as mutable using `let mut {} = ...`", name)))
+            },
+            ir::InstKind::Field { value, .. } => self.expect_assignable(value),
+            // pointers to immutable variables point to a copy, so writing through them is allowed
+            ir::InstKind::Dereference { .. } => Some(()),
+            _ => self.report(CompilerError::new()
+                    .span(&inst.span)
+                    .reason("invalid assignment target")
+                    .note("only variables, fields and dereferenced pointers can be assigned to"))
+        }
+    }
+
+    /// Asserts whether these arguments satisfy the parameters of this function signature.
+    pub fn expect_signature(&mut self, args : &[ir::Inst], sig : &Signature, span : &Span) -> Option<()> {
+        if args.len() != sig.params.len() {
+            return self.report(CompilerError::new()
+                    .span(span)
+                    .reason(format!("expected {} argument(s) (got {})", sig.params.len(), args.len())));
+        }
+        for (arg, param) in args.iter().zip(&sig.params) {
+            self.expect_type(arg, param)?;
+        }
+        Some(())
+    }
+
+    /// Performs type checking on the items of this module. The types of every item are
+    /// resolved before any function body is checked, so items and type aliases can be used
+    /// before they are defined.
+    pub fn check_module(&mut self, module : &mut ir::Module) -> Option<()> {
+        let src = self.src;
+        for (id, item) in module.items.iter().enumerate() {
+            if let ir::ItemKind::Struct { .. } = item.kind {
+                let name = item.name.render(src);
+                self.types.insert(name, ir::TypeKind::Struct { id, name : name.to_string() });
+            }
+        }
+        for item in &module.items {
+            if let ir::ItemKind::Data { datatype } = &item.kind {
+                self.aliases.insert(item.name.render(src), datatype.kind.clone());
+            }
+        }
+        for item in &mut module.items {
+            if let ir::ItemKind::Data { datatype } = &mut item.kind {
+                datatype.kind = self.find_type(&item.name)?;
+            }
+        }
+        for (id, item) in module.items.iter_mut().enumerate() {
+            if let ir::ItemKind::Struct { fields } = &mut item.kind {
+                let mut field_types : Vec<(&str, ir::TypeKind)> = Vec::new();
+                for field in fields {
+                    let name = field.name.render(src);
+                    if field_types.iter().any(|(other, _)| *other == name) {
+                        self.report(CompilerError::new()
+                                .span(&field.name)
+                                .reason(format!("a field with the name `{}` is already defined", name)))?;
+                    }
+                    self.resolve_type(&mut field.datatype)?;
+                    field_types.push((name, field.datatype.kind.clone()));
+                }
+                self.structs.insert(id, field_types);
+            }
+        }
+        for (id, item) in module.items.iter().enumerate() {
+            if let ir::ItemKind::Struct { .. } = item.kind {
+                self.check_struct_size(id, &item.name)?;
+            }
+        }
+        for (id, item) in module.items.iter_mut().enumerate() {
+            if let Some(owner) = &mut item.owner {
+                self.resolve_type(owner)?;
+                self.declare_associated(id, owner, &item.name)?;
+            }
+            let name = item.name.render(src);
+            match &mut item.kind {
+                ir::ItemKind::Function { params, .. } if ir::is_operator(name) && !(1..=2).contains(&params.len()) => {
+                    self.report(CompilerError::new()
+                            .span(&item.name)
+                            .reason(format!("the operator `{}` must take either one or two parameters (got {})", name, params.len())))?;
+                },
+                ir::ItemKind::Definition { .. } if ir::is_operator(name) => {
+                    self.report(CompilerError::new()
+                            .span(&item.name)
+                            .reason(format!("the operator `{}` must be defined as a function", name))
+                            .note(format!("operators are defined as functions with a raw identifier, such as fn `{}`(a : T, b : T) : T", name)))?;
+                },
+                ir::ItemKind::Function { params, return_type, .. } => {
+                    for param in params.iter_mut() {
+                        self.resolve_type(&mut param.datatype)?;
+                        self.locals.insert(param.id, param.datatype.kind.clone());
+                    }
+                    self.resolve_type(return_type)?;
+                    let params = params.iter()
+                            .map(|param| param.datatype.kind.clone())
+                            .collect();
+                    let ret = return_type.kind.clone();
+                    self.functions.insert(id, Signature { params, ret });
+                },
+                ir::ItemKind::Definition { value } => {
+                    self.resolve_type(&mut value.datatype)?;
+                    self.globals.insert(id, value.datatype.kind.clone());
+                },
+                ir::ItemKind::Data { .. } | ir::ItemKind::Struct { .. } => ()
+            }
+        }
+        for item in &mut module.items {
+            match &mut item.kind {
+                ir::ItemKind::Function { return_type, body, .. } => {
+                    self.check_hint(body, Some(&return_type.kind))?;
+                    self.expect_type(body, &return_type.kind)?;
+                    self.substitute(body)?;
+                },
+                ir::ItemKind::Definition { value } => {
+                    self.check(value)?;
+                    self.substitute(value)?;
+                },
+                ir::ItemKind::Data { .. } | ir::ItemKind::Struct { .. } => ()
+            }
+        }
+        self.check_entry(module)
+    }
+
+    /// Asserts that the struct with this id does not contain itself, since it would have an
+    /// infinite size.
+    pub fn check_struct_size(&mut self, id : usize, name : &Span) -> Option<()> {
+        let mut visited = Vec::new();
+        let mut unvisited = vec![id];
+        while let Some(next) = unvisited.pop() {
+            let mut field_types = self.structs[&next].iter()
+                    .map(|(_, ty)| ty)
+                    .collect::<Vec<_>>();
+            while let Some(ty) = field_types.pop() {
+                // pointers and functions have a fixed size, regardless of the types they refer to
+                if matches!(ty, ir::TypeKind::Pointer(_) | ir::TypeKind::Function { .. }) {
+                    continue;
+                }
+                field_types.extend(ty.components());
+                if let ir::TypeKind::Struct { id : field_id, .. } = ty {
+                    if *field_id == id {
+                        return self.report(CompilerError::new()
+                                .span(name)
+                                .reason(format!("the struct `{}` contains itself, so its size is infinite", self.render(name))));
+                    }
+                    if !visited.contains(field_id) {
+                        visited.push(*field_id);
+                        unvisited.push(*field_id);
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Adds the item with this id and name to the associated items of this type. Reports an
+    /// error if the type is not a struct, or if it already has an item with this name.
+    pub fn declare_associated(&mut self, id : usize, owner : &ir::InstType, name : &Span) -> Option<()> {
+        let src = self.src;
+        let item_name = name.render(src);
+        let ty = &owner.kind;
+        let owner = if let ir::TypeKind::Struct { id, .. } = ty {
+            *id
+        } else {
+            return self.report(CompilerError::new()
+                    .span(&owner.span)
+                    .reason(format!("items can only be associated with struct types (got `{}`)", ty)));
+        };
+        let items = self.associated.entry(owner).or_default();
+        if items.iter().any(|(other, _)| *other == item_name) {
+            return self.report(CompilerError::new()
+                    .span(name)
+                    .reason(format!("the type `{}` already has an associated item with the name `{}`", ty, item_name))
+                    .note("consider renaming this item"));
+        }
+        items.push((item_name, id));
+        Some(())
+    }
+
+    /// Returns the id of the item with this name associated with this type, and reports an
+    /// error if there is no such item.
+    pub fn find_associated(&mut self, ty : &ir::TypeKind, name : &Span) -> Option<usize> {
+        let src = self.src;
+        let item_name = name.render(src);
+        let item = if let ir::TypeKind::Struct { id, .. } = ty {
+            self.associated.get(id).and_then(|items| items.iter()
+                    .find(|(other, _)| *other == item_name)
+                    .map(|(_, item)| *item))
+        } else {
+            None
+        };
+        if item.is_some() {
+            item
+        } else {
+            self.report(CompilerError::new()
+                    .span(name)
+                    .reason(format!("the type `{}` has no associated item with the name `{}`", ty, item_name)))
+        }
+    }
+
+    /// Replaces this associated item with a reference to the item it refers to.
+    pub fn resolve_associated(&mut self, inst : &mut ir::Inst) -> Option<()> {
+        if let ir::InstKind::Associated { datatype, name } = &mut inst.kind {
+            self.resolve_type(datatype)?;
+            let id = self.find_associated(&datatype.kind, name)?;
+            inst.kind = ir::InstKind::Global { id };
+        }
+        Some(())
+    }
+
+    /// Returns the index and type of the field with this name, and reports an error if
+    /// this struct has no such field.
+    pub fn find_field(&mut self, ty : &ir::TypeKind, name : &Span) -> Option<(usize, ir::TypeKind)> {
+        let src = self.src;
+        let field_name = name.render(src);
+        let field = if let ir::TypeKind::Struct { id, .. } = ty {
+            self.structs[id].iter()
+                    .position(|(field, _)| *field == field_name)
+                    .map(|i| (i, self.structs[id][i].1.clone()))
+        } else {
+            None
+        };
+        if field.is_some() {
+            field
+        } else {
+            self.report(CompilerError::new()
+                    .span(name)
+                    .reason(format!("the type `{}` has no field with the name `{}`", ty, field_name)))
+        }
+    }
+
+    /// Asserts that this module defines a `main` function without parameters, which returns an integer.
+    pub fn check_entry(&mut self, module : &ir::Module) -> Option<()> {
+        let item = if let Some(id) = module.find_item("main", self.src) {
+            &module.items[id]
+        } else {
+            return self.report(CompilerError::new()
+                    .reason("a program must define a `main` function")
+                    .note("consider adding `fn main() : int32 { 0 }`"));
+        };
+        match &item.kind {
+            ir::ItemKind::Function { params, .. } if !params.is_empty() => self.report(CompilerError::new()
+                    .span(&item.name)
+                    .reason("the `main` function cannot have parameters")),
+            ir::ItemKind::Function { return_type, .. } if !int_types!().contains(&return_type.kind) => {
+                self.report(CompilerError::new()
+                        .span(&return_type.span)
+                        .reason(format!("the `main` function must return an integer (got `{}`)", return_type.kind)))
+            },
+            ir::ItemKind::Function { .. } => Some(()),
+            _ => self.report(CompilerError::new()
+                    .span(&item.name)
+                    .reason("`main` must be a function"))
+        }
+    }
+
     /// Performs type checking on this instruction and returns `None` if it is poorly-typed.
     pub fn check(&mut self, inst : &mut ir::Inst) -> Option<()> {
+        self.check_hint(inst, None)
+    }
+
+    /// Performs type checking on this instruction, using an optional hint for the type expected
+    /// by its context. Other types are inferred by unification, so the hint is only used to infer
+    /// the types of struct literals.
+    pub fn check_hint(&mut self, inst : &mut ir::Inst, hint : Option<&ir::TypeKind>) -> Option<()> {
+        self.resolve_type(&mut inst.datatype)?;
+        // negated literals are checked as a whole, so that `-128` fits into `int8`
+        if let Some(value) = negated_literal(inst, self.src()) {
+            inst.kind = ir::InstKind::Integral { value };
+        }
         let span = &inst.span;
-        let datatype = &mut inst.datatype;
-        match &inst.kind {
+        match &mut inst.kind {
             ir::InstKind::Variable => self.report(
-                    CompilerError::unimplemented("type checking variables").span(&span))?,
+                    CompilerError::unreachable("unresolved variable").span(span))?,
+            ir::InstKind::Local { id } => {
+                let ty = if let Some(ty) = self.locals.get(id) {
+                    ty.clone()
+                } else {
+                    self.report(CompilerError::unreachable("unbound local variable").span(span))?
+                };
+                self.assign_type(inst, ty)?;
+            },
+            ir::InstKind::Global { id } => {
+                let ty = if let Some(ty) = self.globals.get(id) {
+                    ty.clone()
+                } else if self.functions.contains_key(id) {
+                    self.report(CompilerError::unimplemented("first-class functions").span(span))?
+                } else {
+                    self.report(CompilerError::new()
+                            .span(span)
+                            .reason(format!("expected a value (got the type `{}`)", self.render(span))))?
+                };
+                self.assign_type(inst, ty)?;
+            },
+            ir::InstKind::Associated { .. } => {
+                self.resolve_associated(inst)?;
+                return self.check_hint(inst, hint);
+            },
+            ir::InstKind::Method { .. } => self.report(
+                    CompilerError::unreachable("method outside of a function call").span(span))?,
+            ir::InstKind::Builtin { .. } => self.report(
+                    CompilerError::unimplemented("first-class operators").span(span))?,
             ir::InstKind::Integral { .. } => {
-                if let ir::TypeKind::Variable = datatype.kind {
-                    datatype.kind = self.find_type(&datatype.span)?;
-                } else if let ir::TypeKind::Infer = datatype.kind {
-                    datatype.kind = ir::TypeKind::Int(32);
+                let ty = self.fresh_var(ir::TypeClass::Integral);
+                self.assign_type(inst, ty)?;
+                self.expect_class(inst, ir::TypeClass::Integral)?;
+            },
+            ir::InstKind::Float { .. } => {
+                let ty = self.fresh_var(ir::TypeClass::Float);
+                self.assign_type(inst, ty)?;
+                self.expect_class(inst, ir::TypeClass::Float)?;
+            },
+            ir::InstKind::Boolean { .. } => self.assign_type(inst, ir::TypeKind::Bool)?,
+            ir::InstKind::Char { .. } => self.assign_type(inst, ir::TypeKind::Char)?,
+            ir::InstKind::String { .. } => self.assign_type(inst, ir::TypeKind::Str)?,
+            ir::InstKind::FunctionApp { callsite, args } => {
+                let mut checked = 0;
+                if let ir::InstKind::Method { name } = &callsite.kind {
+                    let name = name.clone();
+                    self.check(&mut args[0])?;
+                    let mut receiver = self.prune(&args[0].datatype.kind);
+                    while let ir::TypeKind::Pointer(ty) = receiver {
+                        receiver = *ty;
+                    }
+                    let id = self.find_associated(&receiver, &name)?;
+                    callsite.kind = ir::InstKind::Global { id };
+                    checked = 1;
                 }
-                self.expect_type(inst, int_types!())?;
+                self.resolve_associated(callsite)?;
+                let sig = match callsite.kind {
+                    ir::InstKind::Builtin { op : ir::BuiltinOp::Print } => {
+                        for arg in args.iter_mut() {
+                            self.check_hint(arg, Some(&ir::TypeKind::Str))?;
+                        }
+                        Signature { params : vec![ir::TypeKind::Str], ret : ir::TypeKind::Empty }
+                    },
+                    ir::InstKind::Builtin { op } if matches!(op, ir::BuiltinOp::ToFloat | ir::BuiltinOp::ToInt) => {
+                        let (from, to) = if op == ir::BuiltinOp::ToFloat {
+                            (ir::TypeClass::Numeric, ir::TypeClass::Float)
+                        } else {
+                            (ir::TypeClass::Float, ir::TypeClass::Integral)
+                        };
+                        for arg in args.iter_mut() {
+                            self.check(arg)?;
+                            self.expect_class(arg, from)?;
+                        }
+                        let param = self.fresh_var(from);
+                        Signature { params : vec![param], ret : self.fresh_var(to) }
+                    },
+                    ir::InstKind::Builtin { op } => {
+                        let hint = if op.is_comparison() {
+                            None
+                        } else if let ir::TypeKind::Infer = inst.datatype.kind {
+                            hint.cloned()
+                        } else {
+                            Some(inst.datatype.kind.clone())
+                        };
+                        for arg in args.iter_mut() {
+                            self.check_hint(arg, hint.as_ref())?;
+                        }
+                        for arg in &args[1..] {
+                            self.expect_equal_types(&args[0], arg)?;
+                        }
+                        let operand = self.prune(&args[0].datatype.kind);
+                        // booleans support logical operators, and characters can be compared
+                        if !(op.is_logical() && operand == ir::TypeKind::Bool
+                                || op.is_comparison() && operand == ir::TypeKind::Char) {
+                            let class = if op.is_arithmetic() {
+                                ir::TypeClass::Numeric
+                            } else {
+                                ir::TypeClass::Integral
+                            };
+                            self.expect_class(&args[0], class)?;
+                        }
+                        builtin_signature(op, &self.prune(&operand))
+                    },
+                    ir::InstKind::Global { id } if self.functions.contains_key(&id) => {
+                        let sig = self.functions[&id].clone();
+                        let name = self.render(&callsite.span);
+                        if ir::is_operator(name) && args.len() != sig.params.len() {
+                            self.report(CompilerError::new()
+                                    .span(span)
+                                    .reason(format!("the operator `{}` takes {} operand(s) (got {})", name, sig.params.len(), args.len())))?;
+                        }
+                        for (arg, param) in args.iter_mut().zip(&sig.params).skip(checked) {
+                            self.check_hint(arg, Some(param))?;
+                        }
+                        sig
+                    },
+                    _ => self.report(CompilerError::unimplemented("first-class functions").span(&callsite.span))?
+                };
+                self.expect_signature(args, &sig, span)?;
+                self.assign_type(inst, sig.ret)?;
+            },
+            ir::InstKind::Struct { fields } => {
+                let ty = if let ir::TypeKind::Infer = inst.datatype.kind {
+                    hint.cloned()
+                } else {
+                    Some(inst.datatype.kind.clone())
+                };
+                let ty = match ty.map(|ty| self.prune(&ty)) {
+                    Some(ty @ ir::TypeKind::Struct { .. }) => ty,
+                    None | Some(ir::TypeKind::Unknown { .. }) => self.report(CompilerError::new()
+                            .span(span)
+                            .reason("unable to infer the type of this struct literal")
+                            .note("consider adding a type annotation"))?,
+                    Some(ty) => self.report(CompilerError::new()
+                            .span(span)
+                            .reason(format!("expected a value of type `{}` (got a struct literal)", ty)))?
+                };
+                let mut indices = Vec::new();
+                for (name, index, value) in fields.iter_mut() {
+                    let (field_index, field_ty) = self.find_field(&ty, name)?;
+                    if indices.contains(&field_index) {
+                        self.report(CompilerError::new()
+                                .span(name)
+                                .reason(format!("the field `{}` is already initialised", self.render(name))))?;
+                    }
+                    self.check_hint(value, Some(&field_ty))?;
+                    self.expect_type(value, &field_ty)?;
+                    *index = field_index;
+                    indices.push(field_index);
+                }
+                if let ir::TypeKind::Struct { id, .. } = &ty {
+                    let missing = self.structs[id].iter()
+                            .enumerate()
+                            .filter(|(i, _)| !indices.contains(i))
+                            .map(|(_, (name, _))| format!("`{}`", name))
+                            .collect::<Vec<_>>();
+                    if !missing.is_empty() {
+                        self.report(CompilerError::new()
+                                .span(span)
+                                .reason(format!("missing field(s) {} in struct literal of type `{}`", missing.join(", "), ty)))?;
+                    }
+                }
+                self.assign_type(inst, ty)?;
+            },
+            ir::InstKind::Field { value, name, index } => {
+                self.check(value)?;
+                let value_ty = self.prune(&value.datatype.kind);
+                let (field_index, ty) = self.find_field(&value_ty, name)?;
+                *index = field_index;
+                self.assign_type(inst, ty)?;
             },
-            ir::InstKind::FunctionApp { .. } => self.report(
-                    CompilerError::unimplemented("type checking function application").span(&span))?,
+            ir::InstKind::Cast { value, target } => {
+                self.resolve_type(target)?;
+                self.check(value)?;
+                // integer literals take on the type they are cast to, so that they are checked to fit into it
+                if let ir::TypeKind::Char = target.kind {
+                    self.expect_type(value, &ir::TypeKind::UInt(8))?;
+                } else if matches!(value.kind, ir::InstKind::Integral { .. })
+                        && ir::TypeClass::Integral.admits(&target.kind) {
+                    self.expect_type(value, &target.kind)?;
+                }
+                let from = self.prune(&value.datatype.kind);
+                if !is_valid_cast(&from, &target.kind) {
+                    self.report(CompilerError::new()
+                            .span(span)
+                            .reason(format!("cannot cast a value of type `{}` to the type `{}`", from, target.kind))
+                            .note("only numbers, booleans and characters can be cast, and only `uint8` values can be cast to `char`"))?;
+                }
+                let ty = target.kind.clone();
+                self.assign_type(inst, ty)?;
+            },
+            ir::InstKind::Reference { value } => {
+                let hint = if let ir::TypeKind::Infer = inst.datatype.kind {
+                    hint
+                } else {
+                    Some(&inst.datatype.kind)
+                };
+                let hint = if let Some(ir::TypeKind::Pointer(ty)) = hint.map(|ty| self.prune(ty)) {
+                    Some(ty.as_ref().clone())
+                } else {
+                    None
+                };
+                self.check_hint(value, hint.as_ref())?;
+                let ty = ir::TypeKind::Pointer(Box::new(value.datatype.kind.clone()));
+                self.assign_type(inst, ty)?;
+            },
+            ir::InstKind::Dereference { value } => {
+                let hint = if let ir::TypeKind::Infer = inst.datatype.kind {
+                    hint.cloned()
+                } else {
+                    Some(inst.datatype.kind.clone())
+                };
+                let hint = hint.map(|ty| ir::TypeKind::Pointer(Box::new(ty)));
+                self.check_hint(value, hint.as_ref())?;
+                let ty = match self.prune(&value.datatype.kind) {
+                    ir::TypeKind::Pointer(ty) => *ty,
+                    ty => self.report(CompilerError::new()
+                            .span(&value.span)
+                            .reason(format!("cannot dereference a value of type `{}`", ty))
+                            .note("only pointers can be dereferenced"))?
+                };
+                self.assign_type(inst, ty)?;
+            },
+            ir::InstKind::Block { stmts, value } => {
+                for stmt in stmts {
+                    self.check(stmt)?;
+                }
+                let ty = if let Some(value) = value {
+                    self.check_hint(value, hint)?;
+                    value.datatype.kind.clone()
+                } else {
+                    ir::TypeKind::Empty
+                };
+                self.assign_type(inst, ty)?;
+            },
+            ir::InstKind::If { condition, on_true, on_false } => {
+                self.check_hint(condition, Some(&ir::TypeKind::Bool))?;
+                self.expect_type(condition, &ir::TypeKind::Bool)?;
+                let hint = if let ir::TypeKind::Infer = inst.datatype.kind {
+                    hint.cloned()
+                } else {
+                    Some(inst.datatype.kind.clone())
+                };
+                self.check_hint(on_true, hint.as_ref())?;
+                let hint = match (hint, self.prune(&on_true.datatype.kind)) {
+                    (Some(ty), _) => Some(ty),
+                    (None, ir::TypeKind::Void) => None,
+                    (None, ty) => Some(ty)
+                };
+                self.check_hint(on_false, hint.as_ref())?;
+                let ty = match (self.prune(&on_true.datatype.kind), self.prune(&on_false.datatype.kind)) {
+                    (ir::TypeKind::Void, ty) | (ty, ir::TypeKind::Void) => ty,
+                    (ty, _) => {
+                        let ty = ty.clone();
+                        self.expect_equal_types(on_true, on_false)?;
+                        ty
+                    }
+                };
+                self.assign_type(inst, ty)?;
+            },
+            ir::InstKind::Assign { target, value } => {
+                self.check(target)?;
+                self.expect_assignable(target)?;
+                let ty = target.datatype.kind.clone();
+                self.check_hint(value, Some(&ty))?;
+                self.expect_type(value, &ty)?;
+                self.assign_type(inst, ir::TypeKind::Empty)?;
+            },
+            ir::InstKind::Loop { body } => {
+                self.loops += 1;
+                let result = self.check(body);
+                self.loops -= 1;
+                result?;
+                self.assign_type(inst, ir::TypeKind::Empty)?;
+            },
+            ir::InstKind::Break | ir::InstKind::Continue => {
+                if self.loops == 0 {
+                    let keyword = if let ir::InstKind::Break = inst.kind { "break" } else { "continue" };
+                    self.report(CompilerError::new()
+                            .span(span)
+                            .reason(format!("`{}` cannot be used outside of a loop", keyword)))?;
+                }
+                self.assign_type(inst, ir::TypeKind::Void)?;
+            },
+            ir::InstKind::Let { id, mutable, value, body, .. } => {
+                self.check(value)?;
+                self.locals.insert(*id, value.datatype.kind.clone());
+                if *mutable {
+                    self.mutable.insert(*id);
+                }
+                self.check_hint(body, hint)?;
+                let ty = body.datatype.kind.clone();
+                self.assign_type(inst, ty)?;
+            }
         }
         Some(())
     }
 }
 
 /// Performs type checking on this IR. Returns validated IR.
-pub fn check(mut inst : ir::Inst, src : &str, issues : &mut IssueTracker) -> Option<ir::Inst> {
+pub fn check(mut module : ir::Module, src : &str, issues : &mut IssueTracker) -> Option<ir::Module> {
     let mut tc = TypeChecker::new(src, issues);
-    tc.check(&mut inst)?;
-    Some(inst)
+    tc.check_module(&mut module)?;
+    Some(module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ir::{ TypeClass, TypeKind };
+    use libcosyc_parse::build_ast;
+
+    /// Type checks this source, and returns the reason of the first error reported.
+    fn check(src : &str) -> Result<(), String> {
+        let mut issues = IssueTracker::default();
+        match build_ast(src, &mut issues).and_then(|ast| crate::generate_ir(ast, src, &mut issues)) {
+            Some(_) => Ok(()),
+            None => Err(issues.get_errors()[0].reason.clone())
+        }
+    }
+
+    #[test]
+    fn let_bindings_have_the_type_of_their_value() {
+        assert_eq!(check("fn main() : uint8 { let x = 1 : uint8; x }"), Ok(()));
+        assert_eq!(check("fn main() : int32 { let x = 1 : uint8; x }"),
+                Err("expected a value of type `int32` (got `uint8`)".to_string()));
+    }
+
+    #[test]
+    fn let_bindings_shadow_earlier_bindings() {
+        assert_eq!(check("fn main() : int64 { let x = 1 : uint8; let x = 2 : int64; x }"), Ok(()));
+    }
+
+    #[test]
+    fn let_bindings_are_scoped_to_their_body() {
+        assert_eq!(check("fn main() : int32 { let y = (let x = 1; x); x }"),
+                Err("a variable with the name `x` does not exist in the current context".to_string()));
+    }
+
+    #[test]
+    fn operands_of_built_in_operators_share_a_type() {
+        assert_eq!(check("fn main() : uint8 { let x = 1 : uint8; x + 2 }"), Ok(()));
+        let err = check("fn main() : uint8 { (1 : uint8) + (2 : int64) }").unwrap_err();
+        assert!(err.starts_with("expected a value of type `uint8`"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_operators_are_reported() {
+        assert_eq!(check("fn main() : int32 { 1 +> 2 }"),
+                Err("an operator with the name `+>` taking 2 argument(s) does not exist in the current context".to_string()));
+    }
+
+    #[test]
+    fn items_can_be_used_before_their_definition() {
+        assert_eq!(check("fn main() : int32 { double(limit) }\n\
+                fn double(x : int32) : int32 { x * 2 }\n\
+                def limit : int32 = 10;"), Ok(()));
+    }
+
+    #[test]
+    fn items_cannot_be_defined_twice() {
+        assert_eq!(check("fn main() : int32 { 1 }\nfn main() : int32 { 2 }"),
+                Err("an item with the name `main` is already defined".to_string()));
+    }
+
+    #[test]
+    fn function_arguments_are_counted() {
+        assert_eq!(check("fn f(x : int32) : int32 { x }\nfn main() : int32 { f(1, 2) }"),
+                Err("expected 1 argument(s) (got 2)".to_string()));
+    }
+
+    #[test]
+    fn programs_require_a_main_function() {
+        assert_eq!(check("fn f() : int32 { 1 }"),
+                Err("a program must define a `main` function".to_string()));
+    }
+
+    #[test]
+    fn struct_literals_initialise_every_field() {
+        assert_eq!(check("data Point = struct { x : int32, y : int32 };\n\
+                fn main() : int32 { (struct { y = 2, x = 1 } : Point).x }"), Ok(()));
+        assert_eq!(check("data Point = struct { x : int32, y : int32 };\n\
+                fn main() : int32 { (struct { x = 1 } : Point).x }"),
+                Err("missing field(s) `y` in struct literal of type `Point`".to_string()));
+    }
+
+    #[test]
+    fn struct_literals_initialise_fields_once() {
+        assert_eq!(check("data Point = struct { x : int32, y : int32 };\n\
+                fn main() : int32 { (struct { x = 1, x = 2, y = 3 } : Point).x }"),
+                Err("the field `x` is already initialised".to_string()));
+    }
+
+    #[test]
+    fn fields_must_exist_on_their_struct() {
+        assert_eq!(check("data Point = struct { x : int32, y : int32 };\n\
+                fn main() : int32 { (struct { x = 1, y = 2 } : Point).z }"),
+                Err("the type `Point` has no field with the name `z`".to_string()));
+    }
+
+    #[test]
+    fn structs_cannot_contain_themselves() {
+        assert_eq!(check("data List = struct { next : List };\nfn main() : int32 { 1 }"),
+                Err("the struct `List` contains itself, so its size is infinite".to_string()));
+    }
+
+    #[test]
+    fn references_point_to_the_type_of_their_value() {
+        assert_eq!(check("fn deref(p : ^int64) : int64 { p^ }\n\
+                fn main() : int64 { let x = 1 : int64; deref(^x) }"), Ok(()));
+        let err = check("fn deref(p : ^int64) : int64 { p^ }\n\
+                fn main() : int64 { let x = 1 : int8; deref(^x) }").unwrap_err();
+        assert!(err.starts_with("expected a value of type `^int64`"), "{}", err);
+    }
+
+    #[test]
+    fn only_pointers_can_be_dereferenced() {
+        assert_eq!(check("fn main() : int32 { let x = 1; x^ }"),
+                Err("cannot dereference a value of type `{integer}`".to_string()));
+    }
+
+    #[test]
+    fn methods_are_found_on_the_type_of_their_receiver() {
+        assert_eq!(check("data Point = struct { x : int32, y : int32 };\n\
+                impl Point { fn sum(p : Point) : int32 { p.x + p.y } }\n\
+                fn main() : int32 { (struct { x = 1, y = 2 } : Point).sum() }"), Ok(()));
+        assert_eq!(check("data Point = struct { x : int32, y : int32 };\n\
+                impl Point { fn sum(p : ^Point) : int32 { p^.x + p^.y } }\n\
+                fn main() : int32 { let p = struct { x = 1, y = 2 } : Point; (^p).sum() }"), Ok(()));
+    }
+
+    #[test]
+    fn associated_items_are_found_by_path() {
+        assert_eq!(check("data Point = struct { x : int32, y : int32 };\n\
+                impl Point { def origin : Point = struct { x = 0, y = 0 }; }\n\
+                fn main() : int32 { Point::origin.x }"), Ok(()));
+    }
+
+    #[test]
+    fn unknown_associated_items_are_reported() {
+        assert_eq!(check("data Point = struct { x : int32, y : int32 };\n\
+                fn main() : int32 { (struct { x = 1, y = 2 } : Point).sum() }"),
+                Err("the type `Point` has no associated item with the name `sum`".to_string()));
+    }
+
+    #[test]
+    fn associated_items_cannot_be_defined_twice() {
+        assert_eq!(check("data Point = struct { x : int32, y : int32 };\n\
+                impl Point { def zero : int32 = 0; def zero : int32 = 1; }\n\
+                fn main() : int32 { Point::zero }"),
+                Err("the type `Point` already has an associated item with the name `zero`".to_string()));
+    }
+
+    #[test]
+    fn user_defined_operators_are_applied_like_functions() {
+        assert_eq!(check("fn `+>`(a : int32, b : int32) : int32 { a * b }\n\
+                fn main() : int32 { 1 +> 2 }"), Ok(()));
+    }
+
+    #[test]
+    fn operators_take_one_or_two_parameters() {
+        assert_eq!(check("fn `+>`(a : int32, b : int32, c : int32) : int32 { a }\n\
+                fn main() : int32 { 1 }"),
+                Err("the operator `+>` must take either one or two parameters (got 3)".to_string()));
+    }
+
+    #[test]
+    fn operators_are_applied_to_their_number_of_operands() {
+        assert_eq!(check("fn `!!`(a : int32) : int32 { a }\n\
+                fn main() : int32 { 1 !! 2 }"),
+                Err("the operator `!!` takes 1 operand(s) (got 2)".to_string()));
+    }
+
+    #[test]
+    fn built_in_operators_cannot_be_redefined() {
+        assert_eq!(check("fn `+`(a : int32, b : int32) : int32 { a }\n\
+                fn main() : int32 { 1 }"),
+                Err("the built-in operator `+` cannot be redefined".to_string()));
+    }
+
+    #[test]
+    fn blocks_have_the_type_of_their_value() {
+        assert_eq!(check("fn main() : uint8 { 1; 2; 3 : uint8 }"), Ok(()));
+        assert_eq!(check("fn main() : int32 { { 1; 2 : uint8 } }"),
+                Err("expected a value of type `int32` (got `uint8`)".to_string()));
+    }
+
+    #[test]
+    fn blocks_without_a_value_are_empty() {
+        assert_eq!(check("fn main() : int32 { 1; }"),
+                Err("expected a value of type `int32` (got `()`)".to_string()));
+    }
+
+    #[test]
+    fn conditions_must_be_booleans() {
+        assert_eq!(check("fn main() : int32 { if 1 < 2 then 3 else 4 }"), Ok(()));
+        assert_eq!(check("fn main() : int32 { if 1 then 3 else 4 }"),
+                Err("expected a value of type `bool` (got `{integer}`)".to_string()));
+    }
+
+    #[test]
+    fn branches_of_conditionals_share_a_type() {
+        assert_eq!(check("fn main() : int32 { if true then 1 else 2 : uint8 }"),
+                Err("expected a value of type `int32` (got `uint8`)".to_string()));
+    }
+
+    #[test]
+    fn conditionals_without_else_are_empty() {
+        assert_eq!(check("fn main() : int32 { if true then { 1; }; 2 }"), Ok(()));
+        assert_eq!(check("fn main() : int32 { if true then 1 }"),
+                Err("expected a value of type `{integer}` (got `()`)".to_string()));
+    }
+
+    #[test]
+    fn mutable_variables_can_be_assigned_to() {
+        assert_eq!(check("fn main() : int32 { var x = 1; x = 2; let mut y = x; y = 3; y }"), Ok(()));
+    }
+
+    #[test]
+    fn immutable_variables_cannot_be_assigned_to() {
+        assert_eq!(check("fn main() : int32 { let x = 1; x = 2; x }"),
+                Err("cannot assign to the immutable variable `x`".to_string()));
+    }
+
+    #[test]
+    fn only_places_can_be_assigned_to() {
+        assert_eq!(check("fn main() : int32 { 1 = 2; 3 }"),
+                Err("invalid assignment target".to_string()));
+    }
+
+    #[test]
+    fn break_and_continue_must_be_inside_a_loop() {
+        assert_eq!(check("fn main() : int32 { loop { if true then break else continue }; 1 }"), Ok(()));
+        assert_eq!(check("fn main() : int32 { break; 1 }"),
+                Err("`break` cannot be used outside of a loop".to_string()));
+        assert_eq!(check("fn main() : int32 { continue; 1 }"),
+                Err("`continue` cannot be used outside of a loop".to_string()));
+    }
+
+    #[test]
+    fn conditionals_take_the_type_of_branches_which_do_not_break() {
+        assert_eq!(check("fn main() : int32 { var y = 0 : uint8; loop { y = if y == 3 then break else y + 1; }; 1 }"), Ok(()));
+        assert_eq!(check("fn main() : int32 { var y = 0 : uint8; loop { y = if y == 3 then break else 1 : int64; }; 1 }"),
+                Err("expected a value of type `uint8` (got `int64`)".to_string()));
+    }
+
+    #[test]
+    fn negated_groupings_are_not_literals() {
+        assert_eq!(check("fn main() : int8 { - 128 }"), Ok(()));
+        assert_eq!(check("fn main() : int8 { -(128) }"),
+                Err("the literal `128` does not fit into the type `int8`".to_string()));
+        assert_eq!(check("fn main() : int8 { -(127) }"), Ok(()));
+    }
+
+    #[test]
+    fn pointers_to_immutable_variables_can_be_written_through() {
+        assert_eq!(check("fn main() : int32 { let x = 41; let p = ^x; p^ = 5; x }"), Ok(()));
+        assert_eq!(check("fn main() : int32 { let x = 41; x = 5; x }"),
+                Err("cannot assign to the immutable variable `x`".to_string()));
+    }
+
+    #[test]
+    fn numeric_variables_are_solved_by_members_of_their_class() {
+        let mut issues = IssueTracker::default();
+        let mut checker = TypeChecker::new("", &mut issues);
+        let int = checker.fresh_var(TypeClass::Integral);
+        assert!(!checker.unify(&int, &TypeKind::Float(64)));
+        assert!(checker.unify(&int, &TypeKind::UInt(8)));
+        assert_eq!(checker.prune(&int), TypeKind::UInt(8));
+        let float = checker.fresh_var(TypeClass::Float);
+        assert!(!checker.unify(&float, &TypeKind::Int(32)));
+        assert!(checker.unify(&TypeKind::Float(32), &float));
+        assert_eq!(checker.prune(&float), TypeKind::Float(32));
+    }
+
+    #[test]
+    fn unified_variables_keep_the_more_restrictive_class() {
+        let mut issues = IssueTracker::default();
+        let mut checker = TypeChecker::new("", &mut issues);
+        let numeric = checker.fresh_var(TypeClass::Numeric);
+        let float = checker.fresh_var(TypeClass::Float);
+        assert!(checker.unify(&numeric, &float));
+        assert!(matches!(checker.prune(&numeric), TypeKind::Unknown { class : TypeClass::Float, .. }));
+        assert!(!checker.unify(&numeric, &TypeKind::Int(32)));
+        let int = checker.fresh_var(TypeClass::Integral);
+        assert!(!checker.unify(&int, &numeric));
+        assert!(checker.unify(&numeric, &TypeKind::Float(64)));
+        assert_eq!(checker.prune(&float), TypeKind::Float(64));
+    }
+
+    #[test]
+    fn unsolved_numeric_variables_default_to_int32_or_float64() {
+        let mut issues = IssueTracker::default();
+        let mut checker = TypeChecker::new("", &mut issues);
+        let mut int = checker.fresh_var(TypeClass::Numeric);
+        let mut float = checker.fresh_var(TypeClass::Float);
+        let mut any = checker.fresh_var(TypeClass::Any);
+        assert!(checker.default_numeric(&mut int));
+        assert!(checker.default_numeric(&mut float));
+        assert!(!checker.default_numeric(&mut any));
+        assert_eq!(int, TypeKind::Int(32));
+        assert_eq!(float, TypeKind::Float(64));
+    }
+
+    #[test]
+    fn variables_cannot_contain_themselves() {
+        let mut issues = IssueTracker::default();
+        let mut checker = TypeChecker::new("", &mut issues);
+        let var = checker.fresh_var(TypeClass::Any);
+        assert!(!checker.unify(&var, &TypeKind::Pointer(Box::new(var.clone()))));
+    }
+
+    #[test]
+    fn variables_are_inferred_from_their_uses() {
+        assert_eq!(check("fn main() : int64 { let x = 1; let p = ^x; p^ }"), Ok(()));
+        assert_eq!(check("fn main() : int64 { let x = 1; let y = x : uint8; x }"),
+                Err("expected a value of type `int64` (got `uint8`)".to_string()));
+    }
+
+    #[test]
+    fn negated_literals_are_checked_with_their_sign() {
+        assert_eq!(check("fn main() : int8 { let x : int8 = -128; x }"), Ok(()));
+        assert_eq!(check("fn main() : int64 { -9223372036854775808 }"), Ok(()));
+        assert_eq!(check("fn main() : int8 { -129 }"),
+                Err("the literal `-129` does not fit into the type `int8`".to_string()));
+        assert_eq!(check("fn main() : uint8 { -1 }"),
+                Err("the literal `-1` does not fit into the type `uint8`".to_string()));
+    }
+
+    #[test]
+    fn literals_must_fit_into_their_type() {
+        assert_eq!(check("fn main() : uint8 { 255 }"), Ok(()));
+        assert_eq!(check("fn main() : uint64 { 0xFFFF_FFFF_FFFF_FFFF }"), Ok(()));
+        assert_eq!(check("fn main() : int8 { 128 }"),
+                Err("the literal `128` does not fit into the type `int8`".to_string()));
+        assert_eq!(check("fn main() : uint8 { 0b1_0000_0000 }"),
+                Err("the literal `0b1_0000_0000` does not fit into the type `uint8`".to_string()));
+    }
+
+    #[test]
+    fn numbers_booleans_and_characters_can_be_cast() {
+        assert!(is_valid_cast(&TypeKind::Int(32), &TypeKind::UInt(8)));
+        assert!(is_valid_cast(&TypeKind::UInt(64), &TypeKind::Float(32)));
+        assert!(is_valid_cast(&TypeKind::Float(64), &TypeKind::Int(16)));
+        assert!(is_valid_cast(&TypeKind::Bool, &TypeKind::UInt(8)));
+        assert!(is_valid_cast(&TypeKind::Char, &TypeKind::UInt(32)));
+        assert!(is_valid_cast(&TypeKind::UInt(8), &TypeKind::Char));
+        assert!(is_valid_cast(&TypeKind::Str, &TypeKind::Str));
+    }
+
+    #[test]
+    fn other_casts_are_invalid() {
+        assert!(!is_valid_cast(&TypeKind::Int(32), &TypeKind::Bool));
+        assert!(!is_valid_cast(&TypeKind::Int(8), &TypeKind::Char));
+        assert!(!is_valid_cast(&TypeKind::UInt(32), &TypeKind::Char));
+        assert!(!is_valid_cast(&TypeKind::Bool, &TypeKind::Float(64)));
+        assert!(!is_valid_cast(&TypeKind::Char, &TypeKind::Float(32)));
+        assert!(!is_valid_cast(&TypeKind::Str, &TypeKind::Int(32)));
+        let ptr = TypeKind::Pointer(Box::new(TypeKind::Int(32)));
+        assert!(!is_valid_cast(&ptr, &TypeKind::UInt(64)));
+    }
+
+    #[test]
+    fn lossy_casts_are_narrowing() {
+        assert!(is_narrowing(&TypeKind::Int(32), &TypeKind::Int(8)));
+        assert!(is_narrowing(&TypeKind::Int(8), &TypeKind::UInt(64)));
+        assert!(is_narrowing(&TypeKind::Float(64), &TypeKind::Float(32)));
+        assert!(is_narrowing(&TypeKind::Int(32), &TypeKind::Float(32)));
+        assert!(is_narrowing(&TypeKind::Char, &TypeKind::UInt(16)));
+        assert!(!is_narrowing(&TypeKind::UInt(8), &TypeKind::Int(16)));
+        assert!(!is_narrowing(&TypeKind::Int(32), &TypeKind::Float(64)));
+        assert!(!is_narrowing(&TypeKind::Char, &TypeKind::UInt(32)));
+    }
 }
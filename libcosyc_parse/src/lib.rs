@@ -4,9 +4,9 @@ use libcosyc_diagnostic::{
     source::Span,
     error::{ CompilerError, IssueTracker }
 };
-use libcosyc_scan::{ Lexer, token::TokenKind };
+use libcosyc_scan::{ Lexer, token::{ TokenKind, Assoc } };
 use crate::syntax as ast;
-use std::mem;
+use std::{ mem, collections::HashMap };
 
 fn generate_token(lexer : &mut Lexer) -> TokenKind {
     loop {
@@ -30,7 +30,8 @@ pub struct Parser<'a> {
     issues : &'a mut IssueTracker,
     lexer : Lexer<'a>,
     peeked : TokenKind,
-    span_previous : Span
+    span_previous : Span,
+    fixities : HashMap<String, (u8, Assoc)>
 }
 
 impl<'a> Parser<'a> {
@@ -38,7 +39,8 @@ impl<'a> Parser<'a> {
     pub fn new(mut lexer : Lexer<'a>, issues : &'a mut IssueTracker) -> Self {
         let peeked = generate_token(&mut lexer);
         let span_previous = Span::default();
-        Self { issues, lexer, peeked, span_previous }
+        let fixities = HashMap::new();
+        Self { issues, lexer, peeked, span_previous, fixities }
     }
 
     /// Returns the span of the current lexeme.
@@ -81,14 +83,389 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Returns the precedence and associativity of the peeked operator, if one exists.
+    /// User-defined fixities take priority over the defaults assigned by the lexer.
+    pub fn peek_fixity(&self) -> Option<(u8, Assoc)> {
+        if let TokenKind::Operator { precedence, assoc } = &self.peeked {
+            let fixity = self.fixities
+                    .get(self.substring())
+                    .cloned()
+                    .unwrap_or((*precedence, *assoc));
+            Some(fixity)
+        } else {
+            None
+        }
+    }
+
     /// Returns whether the parser contains additional unparsed tokens.
     pub fn is_empty(&self) -> bool {
         matches!(self.peeked, TokenKind::EoF)
     }
 
+    /// Parses any number of fixity declarations of the form `infixl 6 +>`.
+    /// Declarations affect the operators of all code parsed after them.
+    pub fn parse_fixity_decls(&mut self) -> Option<()> {
+        while self.sat(|x| matches!(x, TokenKind::Infix { .. })) {
+            let assoc = if let TokenKind::Infix { assoc } = self.advance() {
+                assoc
+            } else {
+                Assoc::Left
+            };
+            let keyword = self.span().clone();
+            let digits = self.substring();
+            self.expect(|x| matches!(x, TokenKind::Integral { .. }),
+                    CompilerError::new()
+                            .span(&keyword)
+                            .reason("expected a precedence after fixity declaration")
+                            .note(format!("consider adding a precedence between {} and {}",
+                                    MIN_OPERATOR_PRECEDENCE, MAX_OPERATOR_PRECEDENCE)))?;
+            let precedence_span = self.span().clone();
+            let precedence = match digits.parse::<u8>() {
+                Ok(n) if n <= MAX_OPERATOR_PRECEDENCE => n,
+                _ => self.issues.report_error(CompilerError::new()
+                        .span(&precedence_span)
+                        .reason("invalid operator precedence")
+                        .note(format!("precedences must be between {} and {}",
+                                MIN_OPERATOR_PRECEDENCE, MAX_OPERATOR_PRECEDENCE)))?
+            };
+            if !self.sat(|x| matches!(x, TokenKind::Operator { .. })) {
+                return self.issues.report_error(CompilerError::new()
+                        .span(&keyword.join(&precedence_span))
+                        .reason("expected an operator after fixity declaration"));
+            }
+            let op = self.substring().to_string();
+            self.advance();
+            self.fixities.insert(op, (precedence, assoc));
+        }
+        Some(())
+    }
+
+    /// Parses an identifier and returns its span, otherwise the error is reported.
+    pub fn parse_identifier(&mut self, error : CompilerError) -> Option<Span> {
+        let token = self.expect(TokenKind::is_identifier, error)?;
+        let name = self.span().clone();
+        if let TokenKind::RawIdentifier { closed : false } = token {
+            self.issues.report_error(CompilerError::new()
+                    .span(&name)
+                    .reason("raw identifier is missing a closing accent")
+                    .note("consider adding a closing accent (`)"))
+        } else {
+            Some(name)
+        }
+    }
+
+    /// Entry point for parsing a source file. Parses any number of items and fixity declarations.
+    pub fn parse_module(&mut self) -> Option<ast::Module> {
+        let mut items = Vec::new();
+        loop {
+            self.parse_fixity_decls()?;
+            if self.is_empty() {
+                break;
+            }
+            items.push(self.parse_item()?);
+        }
+        Some(ast::Module { items })
+    }
+
+    /// Parses a top-level `fn`, `def`, `data` or `impl` item.
+    pub fn parse_item(&mut self) -> Option<ast::Item> {
+        let item = match self.advance() {
+            TokenKind::Fn => self.parse_item_fn()?,
+            TokenKind::Def => self.parse_item_def()?,
+            TokenKind::Data => self.parse_item_data()?,
+            TokenKind::Impl => self.parse_item_impl()?,
+            _ => self.issues.report_error(CompilerError::new()
+                    .span(self.span())
+                    .reason("expected a top-level item")
+                    .note("items start with either `fn`, `def`, `data` or `impl`"))?
+        };
+        if self.sat(|x| matches!(x, TokenKind::SemiColon)) {
+            self.advance();
+        }
+        Some(item)
+    }
+
+    /// Parses function items of the form `fn f(x : T, y : U) : V { body }`.
+    pub fn parse_item_fn(&mut self) -> Option<ast::Item> {
+        let keyword = self.span().clone();
+        let name = self.parse_identifier(CompilerError::new()
+                .span(&keyword)
+                .reason("expected a function name after `fn`"))?;
+        self.expect(|x| matches!(x, TokenKind::LeftParen), CompilerError::new()
+                .span(&name)
+                .reason("expected `(` after function name")
+                .note("functions without parameters are written as `f()`"))?;
+        let mut params = Vec::new();
+        while !self.sat(|x| matches!(x, TokenKind::RightParen)) {
+            let param = self.parse_identifier(CompilerError::new()
+                    .span(self.span())
+                    .reason("expected a parameter name"))?;
+            self.expect(|x| matches!(x, TokenKind::Colon), CompilerError::new()
+                    .span(&param)
+                    .reason("expected `:` after parameter name")
+                    .note("parameters require a type annotation"))?;
+            let datatype = self.parse_type()?;
+            params.push(ast::Param { name : param, datatype });
+            if !self.sat(|x| matches!(x, TokenKind::Comma)) {
+                break;
+            }
+            self.advance();
+        }
+        self.expect(|x| matches!(x, TokenKind::RightParen), CompilerError::new()
+                .span(self.span())
+                .reason("expected closing `)` after function parameters"))?;
+        let params_end = self.span().clone();
+        self.expect(|x| matches!(x, TokenKind::Colon), CompilerError::new()
+                .span(&params_end)
+                .reason("expected `:` after function parameters")
+                .note("functions require a return type annotation"))?;
+        let return_type = Box::new(self.parse_type()?);
+        if !self.sat(|x| matches!(x, TokenKind::LeftBrace)) {
+            return self.issues.report_error(CompilerError::new()
+                    .span(&return_type.span)
+                    .reason("expected `{` before function body"));
+        }
+        let body = Box::new(self.parse_expr_block()?);
+        let span = keyword.join(&body.span);
+        let kind = ast::ItemKind::Function { params, return_type, body };
+        Some(ast::Item { span, name, kind })
+    }
+
+    /// Parses constant definitions of the form `def x : T = value;`.
+    pub fn parse_item_def(&mut self) -> Option<ast::Item> {
+        let keyword = self.span().clone();
+        let name = self.parse_identifier(CompilerError::new()
+                .span(&keyword)
+                .reason("expected a definition name after `def`"))?;
+        self.expect(|x| matches!(x, TokenKind::Colon), CompilerError::new()
+                .span(&name)
+                .reason("expected `:` after definition name")
+                .note("definitions require a type annotation"))?;
+        let datatype = Box::new(self.parse_type()?);
+        self.expect(|x| matches!(x, TokenKind::Equals), CompilerError::new()
+                .span(&datatype.span)
+                .reason("expected `=` after definition type"))?;
+        let value = Box::new(self.parse_expr()?);
+        self.expect(|x| matches!(x, TokenKind::SemiColon), CompilerError::new()
+                .span(&value.span)
+                .reason("expected `;` after definition")
+                .note("consider adding `;` after this expression"))?;
+        let span = keyword.join(&value.span);
+        let kind = ast::ItemKind::Definition { datatype, value };
+        Some(ast::Item { span, name, kind })
+    }
+
+    /// Parses named types of the form `data T = U;`.
+    pub fn parse_item_data(&mut self) -> Option<ast::Item> {
+        let keyword = self.span().clone();
+        let name = self.parse_identifier(CompilerError::new()
+                .span(&keyword)
+                .reason("expected a type name after `data`"))?;
+        self.expect(|x| matches!(x, TokenKind::Equals), CompilerError::new()
+                .span(&name)
+                .reason("expected `=` after type name"))?;
+        let datatype = Box::new(self.parse_type()?);
+        self.expect(|x| matches!(x, TokenKind::SemiColon), CompilerError::new()
+                .span(&datatype.span)
+                .reason("expected `;` after data declaration")
+                .note("consider adding `;` after this type"))?;
+        let span = keyword.join(&datatype.span);
+        let kind = ast::ItemKind::Data { datatype };
+        Some(ast::Item { span, name, kind })
+    }
+
+    /// Parses impl blocks of the form `impl T { items }`, which associate functions and
+    /// definitions with the type `T`.
+    pub fn parse_item_impl(&mut self) -> Option<ast::Item> {
+        let keyword = self.span().clone();
+        let name = self.parse_identifier(CompilerError::new()
+                .span(&keyword)
+                .reason("expected a type name after `impl`"))?;
+        self.expect(|x| matches!(x, TokenKind::LeftBrace), CompilerError::new()
+                .span(&name)
+                .reason("expected `{` after type name"))?;
+        let mut items = Vec::new();
+        while !self.sat(|x| matches!(x, TokenKind::RightBrace)) {
+            if self.is_empty() {
+                break;
+            }
+            let item = self.parse_item()?;
+            if !matches!(item.kind, ast::ItemKind::Function { .. } | ast::ItemKind::Definition { .. }) {
+                return self.issues.report_error(CompilerError::new()
+                        .span(&item.span)
+                        .reason("only functions and definitions can be associated with a type"));
+            }
+            items.push(item);
+        }
+        self.expect(|x| matches!(x, TokenKind::RightBrace), CompilerError::new()
+                .span(&keyword.join(self.span()))
+                .reason("expected closing `}` at the end of impl block"))?;
+        let span = keyword.join(self.span());
+        let kind = ast::ItemKind::Impl { items };
+        Some(ast::Item { span, name, kind })
+    }
+
     /// Entry point for parsing any expression.
     pub fn parse_expr(&mut self) -> Option<ast::Term> {
-        self.parse_expr_annotation()
+        self.parse_expr_let()
+    }
+
+    /// Parses let bindings of the form `let x = value; body`, and mutable bindings of the
+    /// form `let mut x = value; body` or `var x = value; body`.
+    pub fn parse_expr_let(&mut self) -> Option<ast::Term> {
+        if self.sat(|x| matches!(x, TokenKind::Let | TokenKind::Var)) {
+            self.parse_let(Self::parse_expr)
+        } else {
+            self.parse_expr_if()
+        }
+    }
+
+    /// Parses a let binding `let x = value;`, followed by a body parsed using `parse_body`.
+    fn parse_let(&mut self, parse_body : fn(&mut Self) -> Option<ast::Term>) -> Option<ast::Term> {
+        let mut binder = if let TokenKind::Var = self.advance() { "var" } else { "let" };
+        let mut keyword = self.span().clone();
+        if binder == "let" && self.sat(|x| matches!(x, TokenKind::Mut)) {
+            self.advance();
+            keyword = keyword.join(self.span());
+            binder = "let mut";
+        }
+        let mutable = binder != "let";
+        let name = self.parse_identifier(CompilerError::new()
+                .span(&keyword)
+                .reason(format!("expected an identifier after `{}`", binder)))?;
+        let datatype = if self.sat(|x| matches!(x, TokenKind::Colon)) {
+            self.advance();
+            Some(Box::new(self.parse_type()?))
+        } else {
+            None
+        };
+        self.expect(|x| matches!(x, TokenKind::Equals), CompilerError::new()
+                .span(datatype.as_ref().map_or(&name, |ty| &ty.span))
+                .reason("expected `=` after let binding")
+                .note(if datatype.is_some() {
+                    "consider adding `=` after this type"
+                } else {
+                    "consider adding `=` after this identifier"
+                }))?;
+        let mut value = Box::new(self.parse_expr()?);
+        if let Some(datatype) = datatype {
+            let span = value.span.clone();
+            let kind = ast::TermKind::TypeAnno { value, datatype };
+            value = Box::new(ast::Term { span, kind });
+        }
+        self.expect(|x| matches!(x, TokenKind::SemiColon), CompilerError::new()
+                .span(&value.span)
+                .reason("expected `;` after let binding")
+                .note("consider adding `;` after this expression"))?;
+        let body = Box::new(parse_body(self)?);
+        let span = keyword.join(&body.span);
+        let kind = ast::TermKind::Let { name, mutable, value, body };
+        Some(ast::Term { span, kind })
+    }
+
+    /// Parses conditionals of the form `if c then a else b`, where the `else` branch is optional.
+    pub fn parse_expr_if(&mut self) -> Option<ast::Term> {
+        if !self.sat(|x| matches!(x, TokenKind::If)) {
+            return self.parse_expr_loop();
+        }
+        self.advance();
+        let keyword = self.span().clone();
+        let condition = Box::new(self.parse_expr()?);
+        self.expect(|x| matches!(x, TokenKind::Then), CompilerError::new()
+                .span(&keyword.join(&condition.span))
+                .reason("expected `then` after if condition")
+                .note("conditionals are written `if c then a else b`"))?;
+        let on_true = Box::new(self.parse_expr()?);
+        let on_false = if self.sat(|x| matches!(x, TokenKind::Else)) {
+            self.advance();
+            Some(Box::new(self.parse_expr()?))
+        } else {
+            None
+        };
+        let span = keyword.join(&on_false.as_ref().unwrap_or(&on_true).span);
+        let kind = ast::TermKind::If { condition, on_true, on_false };
+        Some(ast::Term { span, kind })
+    }
+
+    /// Parses loops of the form `while c do body` or `loop body`.
+    pub fn parse_expr_loop(&mut self) -> Option<ast::Term> {
+        if self.sat(|x| matches!(x, TokenKind::While)) {
+            self.advance();
+            let keyword = self.span().clone();
+            let condition = Box::new(self.parse_expr()?);
+            self.expect(|x| matches!(x, TokenKind::Do), CompilerError::new()
+                    .span(&keyword.join(&condition.span))
+                    .reason("expected `do` after while condition")
+                    .note("while loops are written `while c do body`"))?;
+            let body = Box::new(self.parse_expr()?);
+            let span = keyword.join(&body.span);
+            let kind = ast::TermKind::While { condition, body };
+            Some(ast::Term { span, kind })
+        } else if self.sat(|x| matches!(x, TokenKind::Loop)) {
+            self.advance();
+            let keyword = self.span().clone();
+            let body = Box::new(self.parse_expr()?);
+            let span = keyword.join(&body.span);
+            let kind = ast::TermKind::Loop { body };
+            Some(ast::Term { span, kind })
+        } else {
+            self.parse_expr_assign()
+        }
+    }
+
+    /// Parses assignments of the form `target = value`.
+    pub fn parse_expr_assign(&mut self) -> Option<ast::Term> {
+        let target = self.parse_expr_annotation()?;
+        if self.sat(|x| matches!(x, TokenKind::Equals)) {
+            self.advance();
+            let target = Box::new(target);
+            let value = Box::new(self.parse_expr()?);
+            let span = target.span.join(&value.span);
+            let kind = ast::TermKind::Assign { target, value };
+            Some(ast::Term { span, kind })
+        } else {
+            Some(target)
+        }
+    }
+
+    /// Parses block expressions of the form `{ stmt; stmt; value }`.
+    pub fn parse_expr_block(&mut self) -> Option<ast::Term> {
+        self.advance();
+        let brace = self.span().clone();
+        let mut block = self.parse_block_stmts()?;
+        self.expect(|x| matches!(x, TokenKind::RightBrace), CompilerError::new()
+                .span(&brace.join(self.span()))
+                .reason("expected closing `}` at the end of block"))?;
+        block.span = brace.join(self.span());
+        Some(block)
+    }
+
+    /// Parses the statements of a block up until its closing `}`. The block has no value if
+    /// its final statement ends with `;`. Let bindings scope over the rest of the block.
+    fn parse_block_stmts(&mut self) -> Option<ast::Term> {
+        let start = self.span_peek().clone();
+        let mut stmts = Vec::new();
+        let value = loop {
+            if self.is_empty() || self.sat(|x| matches!(x, TokenKind::RightBrace)) {
+                break None;
+            } else if self.sat(|x| matches!(x, TokenKind::Let | TokenKind::Var)) {
+                break Some(Box::new(self.parse_let(Self::parse_block_stmts)?));
+            }
+            let stmt = self.parse_expr()?;
+            if self.sat(|x| matches!(x, TokenKind::SemiColon)) {
+                self.advance();
+                stmts.push(stmt);
+            } else {
+                break Some(Box::new(stmt));
+            }
+        };
+        let span = match (&value, stmts.last()) {
+            (Some(value), _) => start.join(&value.span),
+            (None, Some(_)) => start.join(self.span()),
+            (None, None) => start
+        };
+        let kind = ast::TermKind::Block { stmts, value };
+        Some(ast::Term { span, kind })
     }
 
     /// Parses type annotations.
@@ -97,7 +474,7 @@ impl<'a> Parser<'a> {
         if self.sat(|x| matches!(x, TokenKind::Colon)) {
             self.advance();
             let value = Box::new(value);
-            let datatype = Box::new(self.parse_expr_terminal()?);
+            let datatype = Box::new(self.parse_type()?);
             let span = value.span.join(&datatype.span);
             let kind = ast::TermKind::TypeAnno { value, datatype };
             Some(ast::Term { span, kind })
@@ -109,15 +486,20 @@ impl<'a> Parser<'a> {
     /// Parses a binary operator with this precedence.
     pub fn parse_expr_binary(&mut self, expected_precedence : u8) -> Option<ast::Term> {
         if expected_precedence > MAX_OPERATOR_PRECEDENCE {
-            return self.parse_expr_unary();
+            return self.parse_expr_cast();
         }
         let mut expr = self.parse_expr_binary(expected_precedence + 1)?;
-        while self.sat(|x| matches!(x, TokenKind::Operator { precedence }
-                if *precedence == expected_precedence )) {
+        while let Some((precedence, assoc)) = self.peek_fixity() {
+            if precedence != expected_precedence {
+                break;
+            }
             self.advance();
             let op = self.span().clone();
             let left = Box::new(expr);
-            let right = Box::new(self.parse_expr_binary(expected_precedence + 1)?);
+            let right = Box::new(match assoc {
+                Assoc::Left => self.parse_expr_binary(expected_precedence + 1)?,
+                Assoc::Right => self.parse_expr_binary(expected_precedence)?
+            });
             let span = left.span.join(&right.span);
             let kind = ast::TermKind::BinaryOp { op, left, right };
             expr = ast::Term { span, kind };
@@ -125,23 +507,288 @@ impl<'a> Parser<'a> {
         Some(expr)
     }
 
-    /// Parses unary operators.
+    /// Parses casts of the form `v as T`, which bind more tightly than binary operators.
+    pub fn parse_expr_cast(&mut self) -> Option<ast::Term> {
+        let mut expr = self.parse_expr_unary()?;
+        while self.sat(|x| matches!(x, TokenKind::As)) {
+            self.advance();
+            let value = Box::new(expr);
+            let datatype = Box::new(self.parse_type()?);
+            let span = value.span.join(&datatype.span);
+            let kind = ast::TermKind::Cast { value, datatype };
+            expr = ast::Term { span, kind };
+        }
+        Some(expr)
+    }
+
+    /// Parses unary operators, and references of the form `^v`.
     pub fn parse_expr_unary(&mut self) -> Option<ast::Term> {
-        if self.sat(|x| matches!(x, TokenKind::Operator { .. })) {
+        if self.sat(|x| matches!(x, TokenKind::Caret)) {
+            self.advance();
+            let caret = self.span().clone();
+            let value = Box::new(self.parse_expr_unary()?);
+            let span = caret.join(&value.span);
+            let kind = ast::TermKind::Reference { value };
+            Some(ast::Term { span, kind })
+        } else if self.sat(|x| matches!(x, TokenKind::Operator { .. })) {
             self.advance();
             let op = self.span().clone();
-            let value = Box::new(self.parse_expr_terminal()?);
+            let value = Box::new(self.parse_expr_postfix()?);
             let span = op.join(&value.span);
             let kind = ast::TermKind::UnaryOp { op, value };
             Some(ast::Term { span, kind })
         } else {
-            self.parse_expr_terminal()
+            self.parse_expr_postfix()
+        }
+    }
+
+    /// Parses function calls of the form `f(x, y)`, field accesses of the form `v.x`,
+    /// dereferences of the form `v^`, and associated items of the form `T::x`.
+    pub fn parse_expr_postfix(&mut self) -> Option<ast::Term> {
+        let mut expr = self.parse_expr_terminal()?;
+        loop {
+            if self.sat(|x| matches!(x, TokenKind::LeftParen)) {
+                self.advance();
+                let mut args = Vec::new();
+                while !self.sat(|x| matches!(x, TokenKind::RightParen)) {
+                    args.push(self.parse_expr()?);
+                    if !self.sat(|x| matches!(x, TokenKind::Comma)) {
+                        break;
+                    }
+                    self.advance();
+                }
+                self.expect(|x| matches!(x, TokenKind::RightParen), CompilerError::new()
+                        .span(&expr.span.join(self.span()))
+                        .reason("expected closing `)` after function arguments"))?;
+                let callsite = Box::new(expr);
+                let span = callsite.span.join(self.span());
+                let kind = ast::TermKind::Call { callsite, args };
+                expr = ast::Term { span, kind };
+            } else if self.sat(|x| matches!(x, TokenKind::Dot)) {
+                self.advance();
+                let dot = self.span().clone();
+                // a `.` after an integer is only a decimal point if it is followed by digits, so
+                // `1.` and `1.e5` are reported here rather than as missing or unknown fields
+                if let ast::TermKind::Integral { radix : 10 } = expr.kind {
+                    let name = self.substring();
+                    let exponent = name.starts_with(['e', 'E'])
+                            && name[1..].chars().all(|c| c.is_ascii_digit() || c == '_');
+                    if exponent || !self.sat(TokenKind::is_identifier) {
+                        return self.issues.report_error(CompilerError::new()
+                                .span(&expr.span.join(&dot))
+                                .reason("expected digits after the decimal point")
+                                .note("consider adding a `0` after the decimal point"));
+                    }
+                }
+                let field = self.parse_identifier(CompilerError::new()
+                        .span(&dot)
+                        .reason("expected a field name after `.`"))?;
+                let value = Box::new(expr);
+                let span = value.span.join(&field);
+                let kind = ast::TermKind::Field { value, field };
+                expr = ast::Term { span, kind };
+            } else if self.sat(|x| matches!(x, TokenKind::DoubleColon)) {
+                self.advance();
+                let colons = self.span().clone();
+                let name = self.parse_identifier(CompilerError::new()
+                        .span(&colons)
+                        .reason("expected an item name after `::`"))?;
+                let datatype = if let ast::TermKind::Variable = expr.kind {
+                    let kind = ast::TypeKind::Named;
+                    Box::new(ast::Type { span : expr.span, kind })
+                } else {
+                    return self.issues.report_error(CompilerError::new()
+                            .span(&expr.span)
+                            .reason("expected a type name before `::`"));
+                };
+                let span = datatype.span.join(&name);
+                let kind = ast::TermKind::Associated { datatype, name };
+                expr = ast::Term { span, kind };
+            } else if self.sat(|x| matches!(x, TokenKind::Caret)) {
+                self.advance();
+                let value = Box::new(expr);
+                let span = value.span.join(self.span());
+                let kind = ast::TermKind::Dereference { value };
+                expr = ast::Term { span, kind };
+            } else {
+                break Some(expr);
+            }
+        }
+    }
+
+    /// Returns whether the peeked token is the operator `op`.
+    pub fn sat_operator(&self, op : &str) -> bool {
+        self.sat(|x| matches!(x, TokenKind::Operator { .. })) && self.substring() == op
+    }
+
+    /// Parses type expressions. Function types `T -> U` are right-associative.
+    pub fn parse_type(&mut self) -> Option<ast::Type> {
+        let datatype = self.parse_type_terminal()?;
+        if self.sat_operator("->") {
+            self.advance();
+            let return_type = Box::new(self.parse_type()?);
+            let span = datatype.span.join(self.span());
+            let kind = ast::TypeKind::Function { params : vec![datatype], return_type };
+            Some(ast::Type { span, kind })
+        } else {
+            Some(datatype)
+        }
+    }
+
+    /// Parses named types, pointer types `^T`, array types `[T; N]` and struct types.
+    pub fn parse_type_terminal(&mut self) -> Option<ast::Type> {
+        if self.sat(|x| matches!(x, TokenKind::Caret)) {
+            self.advance();
+            let caret = self.span().clone();
+            let datatype = Box::new(self.parse_type_terminal()?);
+            let span = caret.join(self.span());
+            let kind = ast::TypeKind::Pointer { datatype };
+            Some(ast::Type { span, kind })
+        } else if self.sat(|x| matches!(x, TokenKind::LeftBox)) {
+            self.advance();
+            let left = self.span().clone();
+            let element = Box::new(self.parse_type()?);
+            self.expect(|x| matches!(x, TokenKind::SemiColon), CompilerError::new()
+                    .span(&left.join(&element.span))
+                    .reason("expected `;` after the element type of an array")
+                    .note("array types are written `[T; N]`"))?;
+            let length = Box::new(self.parse_expr_terminal()?);
+            self.expect(|x| matches!(x, TokenKind::RightBox), CompilerError::new()
+                    .span(&left.join(&length.span))
+                    .reason("expected closing `]` after array length"))?;
+            let span = left.join(self.span());
+            let kind = ast::TypeKind::Array { element, length };
+            Some(ast::Type { span, kind })
+        } else if self.sat(|x| matches!(x, TokenKind::Struct)) {
+            self.parse_type_struct()
+        } else if self.sat(TokenKind::is_identifier) {
+            let span = self.parse_identifier(CompilerError::bug()
+                    .span(self.span())
+                    .reason("expected an identifier"))?;
+            let kind = ast::TypeKind::Named;
+            Some(ast::Type { span, kind })
+        } else {
+            self.parse_type_grouping()
+        }
+    }
+
+    /// Parses parenthesised types `(T)`, tuple types `(T, U)` and the parameters of function
+    /// types `(T, U) -> V`.
+    pub fn parse_type_grouping(&mut self) -> Option<ast::Type> {
+        if !self.sat(|x| matches!(x, TokenKind::LeftParen)) {
+            self.advance();
+            return self.issues.report_error(CompilerError::new()
+                    .span(self.span())
+                    .reason("expected a type")
+                    .note("consider removing this token"));
+        }
+        self.advance();
+        let left = self.span().clone();
+        let mut elements = Vec::new();
+        let mut trailing_comma = false;
+        while !self.sat(|x| matches!(x, TokenKind::RightParen)) {
+            elements.push(self.parse_type()?);
+            trailing_comma = self.sat(|x| matches!(x, TokenKind::Comma));
+            if !trailing_comma {
+                break;
+            }
+            self.advance();
+        }
+        self.expect(|x| matches!(x, TokenKind::RightParen), CompilerError::new()
+                .span(&left.join(self.span()))
+                .reason("expected closing `)` at the end of grouping"))?;
+        let span = left.join(self.span());
+        if self.sat_operator("->") {
+            self.advance();
+            let return_type = Box::new(self.parse_type()?);
+            let span = span.join(self.span());
+            let kind = ast::TypeKind::Function { params : elements, return_type };
+            Some(ast::Type { span, kind })
+        } else if elements.len() == 1 && !trailing_comma {
+            elements.pop()
+        } else {
+            let kind = ast::TypeKind::Tuple { elements };
+            Some(ast::Type { span, kind })
+        }
+    }
+
+    /// Parses struct types of the form `struct { x : T }`.
+    pub fn parse_type_struct(&mut self) -> Option<ast::Type> {
+        self.advance();
+        let keyword = self.span().clone();
+        self.expect(|x| matches!(x, TokenKind::LeftBrace), CompilerError::new()
+                .span(&keyword)
+                .reason("expected `{` after `struct`"))?;
+        let mut fields = Vec::new();
+        while !self.sat(|x| matches!(x, TokenKind::RightBrace)) {
+            let name = self.parse_identifier(CompilerError::new()
+                    .span(self.span())
+                    .reason("expected a field name"))?;
+            self.expect(|x| matches!(x, TokenKind::Colon), CompilerError::new()
+                    .span(&name)
+                    .reason("expected `:` after field name")
+                    .note("struct types are written `struct { x : T }`"))?;
+            let datatype = self.parse_type()?;
+            fields.push(ast::FieldType { name, datatype });
+            if !self.sat(|x| matches!(x, TokenKind::Comma)) {
+                break;
+            }
+            self.advance();
+        }
+        self.expect(|x| matches!(x, TokenKind::RightBrace), CompilerError::new()
+                .span(&keyword.join(self.span()))
+                .reason("expected closing `}` at the end of struct"))?;
+        let span = keyword.join(self.span());
+        if fields.is_empty() {
+            return self.issues.report_error(CompilerError::unimplemented("structs without fields")
+                    .span(&span));
         }
+        let kind = ast::TypeKind::Struct { fields };
+        Some(ast::Type { span, kind })
+    }
+
+    /// Parses struct literals of the form `struct { x = value }`.
+    pub fn parse_expr_struct(&mut self) -> Option<ast::Term> {
+        self.advance();
+        let keyword = self.span().clone();
+        self.expect(|x| matches!(x, TokenKind::LeftBrace), CompilerError::new()
+                .span(&keyword)
+                .reason("expected `{` after `struct`"))?;
+        let mut fields = Vec::new();
+        while !self.sat(|x| matches!(x, TokenKind::RightBrace)) {
+            let name = self.parse_identifier(CompilerError::new()
+                    .span(self.span())
+                    .reason("expected a field name"))?;
+            self.expect(|x| matches!(x, TokenKind::Equals), CompilerError::new()
+                    .span(&name)
+                    .reason("expected `=` after field name")
+                    .note("struct literals are written `struct { x = value }`, and struct types can only be used as types"))?;
+            let term = self.parse_expr()?;
+            fields.push(ast::Field { name, term });
+            if !self.sat(|x| matches!(x, TokenKind::Comma)) {
+                break;
+            }
+            self.advance();
+        }
+        self.expect(|x| matches!(x, TokenKind::RightBrace), CompilerError::new()
+                .span(&keyword.join(self.span()))
+                .reason("expected closing `}` at the end of struct"))?;
+        let span = keyword.join(self.span());
+        if fields.is_empty() {
+            return self.issues.report_error(CompilerError::unimplemented("structs without fields")
+                    .span(&span));
+        }
+        let kind = ast::TermKind::StructLit { fields };
+        Some(ast::Term { span, kind })
     }
 
     /// Parses literals and identifiers.
     pub fn parse_expr_terminal(&mut self) -> Option<ast::Term> {
-        if self.sat(TokenKind::is_identifier) {
+        if self.sat(|x| matches!(x, TokenKind::Struct)) {
+            self.parse_expr_struct()
+        } else if self.sat(|x| matches!(x, TokenKind::LeftBrace)) {
+            self.parse_expr_block()
+        } else if self.sat(TokenKind::is_identifier) {
             let kind = match self.advance() {
                 TokenKind::RawIdentifier { closed : false } => {
                     self.issues.report_error(CompilerError::new()
@@ -157,7 +804,22 @@ impl<'a> Parser<'a> {
             let token = self.advance();
             let span = self.span().clone();
             let kind = match token {
-                TokenKind::Integral => ast::TermKind::Integral { radix : 10 },
+                TokenKind::Integral { radix } => ast::TermKind::Integral { radix },
+                TokenKind::Float => ast::TermKind::Float,
+                TokenKind::String { closed : false } => self.issues.report_error(CompilerError::new()
+                        .span(self.span())
+                        .reason("unterminated string literal")
+                        .note("consider adding a closing `\"` before the end of the line"))?,
+                TokenKind::String { .. } => ast::TermKind::String,
+                TokenKind::Char { closed : false } => self.issues.report_error(CompilerError::new()
+                        .span(self.span())
+                        .reason("unterminated character literal")
+                        .note("consider adding a closing `'` before the end of the line"))?,
+                TokenKind::Char { .. } => ast::TermKind::Char,
+                TokenKind::True => ast::TermKind::Boolean { value : true },
+                TokenKind::False => ast::TermKind::Boolean { value : false },
+                TokenKind::Break => ast::TermKind::Break,
+                TokenKind::Continue => ast::TermKind::Continue,
                 _ => self.issues.report_error(CompilerError::bug()
                         .span(self.span())
                         .reason("invalid terminal kind"))?
@@ -196,17 +858,75 @@ impl<'a> Into<Lexer<'a>> for Parser<'a> {
 }
 
 /// Generates the AST of this source code and reports any errors to this `IssueTracker`.
-pub fn build_ast(src : &str, issues : &mut IssueTracker) -> Option<ast::Term> {
+pub fn build_ast(src : &str, issues : &mut IssueTracker) -> Option<ast::Module> {
     let lexer = Lexer::from(src);
     let mut parser = Parser::new(lexer, issues);
-    let program = parser.parse_expr()?;
-    if parser.is_empty() {
-        Some(program)
-    } else {
-        let lexer : Lexer = parser.into();
-        let span : Span = lexer.into();
-        issues.report_error(CompilerError::bug()
-                .span(&span)
-                .reason("unparsed tokens at the end of this file"))
+    parser.parse_module()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the operators of this term as an s-expression, such as `(+ a (* b c))`.
+    fn sexpr(term : &ast::Term, src : &str) -> String {
+        let text = |span : &Span| src[span.begin..span.end].to_string();
+        match &term.kind {
+            ast::TermKind::BinaryOp { op, left, right } => {
+                format!("({} {} {})", text(op), sexpr(left, src), sexpr(right, src))
+            },
+            ast::TermKind::UnaryOp { op, value } => format!("({} {})", text(op), sexpr(value, src)),
+            _ => text(&term.span)
+        }
+    }
+
+    /// Parses the value of the definition at the end of this source, and writes it as an
+    /// s-expression.
+    fn parse(src : &str) -> String {
+        let mut issues = IssueTracker::default();
+        let module = build_ast(src, &mut issues).expect("the source should parse");
+        match &module.items.last().map(|item| &item.kind) {
+            Some(ast::ItemKind::Definition { value, .. }) => sexpr(value, src),
+            _ => panic!("expected a definition")
+        }
+    }
+
+    /// Parses this source, and returns the reason of the first error reported.
+    fn check(src : &str) -> Result<(), String> {
+        let mut issues = IssueTracker::default();
+        match build_ast(src, &mut issues) {
+            Some(_) => Ok(()),
+            None => Err(issues.get_errors()[0].reason.clone())
+        }
+    }
+
+    #[test]
+    fn operators_bind_by_precedence() {
+        assert_eq!(parse("def x : int32 = a + b * c;"), "(+ a (* b c))");
+        assert_eq!(parse("def x : int32 = a * b + c;"), "(+ (* a b) c)");
+        assert_eq!(parse("def x : bool = a + b < c | d;"), "(| (< (+ a b) c) d)");
+    }
+
+    #[test]
+    fn operators_associate_by_fixity() {
+        assert_eq!(parse("def x : int32 = a - b - c;"), "(- (- a b) c)");
+        assert_eq!(parse("def x : int32 = a ** b ** c;"), "(** a (** b c))");
+    }
+
+    #[test]
+    fn fixity_declarations_apply_to_later_operators() {
+        let src = "infixr 6 +>\ndef x : int32 = a +> b +> c * d;";
+        assert_eq!(parse(src), "(+> a (+> b (* c d)))");
+        let src = "infixl 8 <+\ndef x : int32 = a <+ b <+ c * d;";
+        assert_eq!(parse(src), "(* (<+ (<+ a b) c) d)");
+    }
+
+    #[test]
+    fn decimal_points_require_digits() {
+        let reason = Err("expected digits after the decimal point".to_string());
+        assert_eq!(check("def x : float64 = 1.;"), reason);
+        assert_eq!(check("def x : float64 = 1.e5;"), reason);
+        assert_eq!(check("def x : float64 = 1.E+5;"), reason);
+        assert_eq!(check("def x : int32 = 1.f();"), Ok(()));
     }
 }
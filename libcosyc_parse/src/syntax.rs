@@ -7,9 +7,19 @@ pub enum TermKind {
     Integral {
         radix : u8
     },
+    /// A floating point literal `1.5` or `15e-1`.
+    Float,
+    /// A character literal `'c'`, which must contain exactly one character or escape sequence.
+    Char,
+    /// A string literal `"text"`, whose escape sequences are validated when it is desugared.
+    String,
+    /// A boolean literal `true` or `false`.
+    Boolean {
+        value : bool
+    },
     TypeAnno {
         value : Box<Term>,
-        datatype : Box<Term>
+        datatype : Box<Type>
     },
     BinaryOp {
         op : Span,
@@ -19,12 +29,176 @@ pub enum TermKind {
     UnaryOp {
         op : Span,
         value : Box<Term>
+    },
+    Call {
+        callsite : Box<Term>,
+        args : Vec<Term>
+    },
+    /// A cast `value as T`, which converts a value into another type.
+    Cast {
+        value : Box<Term>,
+        datatype : Box<Type>
+    },
+    /// A struct literal `struct { x = a, y = b }`.
+    StructLit {
+        fields : Vec<Field>
+    },
+    /// An item associated with a type `T::name`.
+    Associated {
+        datatype : Box<Type>,
+        name : Span
+    },
+    /// A field access `value.field`. If this is the callsite of a function call, then it
+    /// is a method call `value.method(args)`.
+    Field {
+        value : Box<Term>,
+        field : Span
+    },
+    /// The address of a value `^v`.
+    Reference {
+        value : Box<Term>
+    },
+    /// A dereference `v^`.
+    Dereference {
+        value : Box<Term>
+    },
+    /// A block `{ stmt; stmt; value }`, whose value is missing if the block ends with `;`.
+    Block {
+        stmts : Vec<Term>,
+        value : Option<Box<Term>>
+    },
+    /// A conditional `if c then a else b`. If the `else` branch is missing, then it is
+    /// treated as an empty block.
+    If {
+        condition : Box<Term>,
+        on_true : Box<Term>,
+        on_false : Option<Box<Term>>
+    },
+    /// An assignment `target = value`.
+    Assign {
+        target : Box<Term>,
+        value : Box<Term>
+    },
+    /// A loop `while c do body`, which evaluates its body for as long as the condition holds.
+    While {
+        condition : Box<Term>,
+        body : Box<Term>
+    },
+    /// A loop `loop body`, which evaluates its body until it is broken out of.
+    Loop {
+        body : Box<Term>
+    },
+    /// Exits the innermost loop.
+    Break,
+    /// Skips to the next iteration of the innermost loop.
+    Continue,
+    /// A binding `let x = value; body`. Bindings declared using `let mut` or `var` are mutable.
+    Let {
+        name : Span,
+        mutable : bool,
+        value : Box<Term>,
+        body : Box<Term>
     }
 }
 
+/// Represents a named field of a struct literal.
+#[derive(Debug)]
+pub struct Field {
+    pub name : Span,
+    pub term : Term
+}
+
 /// Represents AST terms.
 #[derive(Debug)]
 pub struct Term {
     pub span : Span,
     pub kind : TermKind
 }
+
+/// Represents a kind of type expression.
+#[derive(Debug)]
+pub enum TypeKind {
+    /// A primitive or user-defined type with this name.
+    Named,
+    /// A pointer type `^T`.
+    Pointer {
+        datatype : Box<Type>
+    },
+    /// A function type `T -> U`. Functions with several parameters have the type `(T, U) -> V`.
+    Function {
+        params : Vec<Type>,
+        return_type : Box<Type>
+    },
+    /// A tuple type `(T, U)`. The empty tuple `()` is the type of statements.
+    Tuple {
+        elements : Vec<Type>
+    },
+    /// An array type `[T; N]`, whose length is an integer literal.
+    Array {
+        element : Box<Type>,
+        length : Box<Term>
+    },
+    /// A struct type `struct { x : T, y : U }`.
+    Struct {
+        fields : Vec<FieldType>
+    }
+}
+
+/// Represents a named field of a struct type.
+#[derive(Debug)]
+pub struct FieldType {
+    pub name : Span,
+    pub datatype : Type
+}
+
+/// Represents AST type expressions.
+#[derive(Debug)]
+pub struct Type {
+    pub span : Span,
+    pub kind : TypeKind
+}
+
+/// Represents a parameter of a function.
+#[derive(Debug)]
+pub struct Param {
+    pub name : Span,
+    pub datatype : Type
+}
+
+/// Represents a kind of top-level item.
+#[derive(Debug)]
+pub enum ItemKind {
+    /// A function `fn f(x : T) : U { body }`.
+    Function {
+        params : Vec<Param>,
+        return_type : Box<Type>,
+        body : Box<Term>
+    },
+    /// A constant definition `def x : T = value;`.
+    Definition {
+        datatype : Box<Type>,
+        value : Box<Term>
+    },
+    /// A named type `data T = U;`.
+    Data {
+        datatype : Box<Type>
+    },
+    /// An impl block `impl T { items }`, whose name is the type `T`.
+    Impl {
+        items : Vec<Item>
+    }
+}
+
+/// Represents top-level items.
+#[derive(Debug)]
+pub struct Item {
+    pub span : Span,
+    pub name : Span,
+    pub kind : ItemKind
+}
+
+/// Represents the items of a single source file.
+#[derive(Debug)]
+pub struct Module {
+    pub items : Vec<Item>
+}
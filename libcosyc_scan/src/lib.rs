@@ -4,13 +4,33 @@ pub mod reader;
 
 use reader::SymbolReader;
 use symbol::SymbolKind;
-use token::TokenKind;
+use token::{ TokenKind, Assoc };
 use libcosyc_diagnostic::source::Span;
 
+/// Returns the default precedence and associativity of an operator, based on its leading characters.
+/// Higher precedences bind more tightly.
+pub fn default_fixity(op : &str) -> (u8, Assoc) {
+    if op.starts_with("->") {
+        return (1, Assoc::Right);
+    } else if op.starts_with("**") {
+        return (8, Assoc::Right);
+    }
+    match op.chars().next() {
+        Some('$') => (0, Assoc::Right),
+        Some('|') => (2, Assoc::Right),
+        Some('&') => (3, Assoc::Right),
+        Some('=') | Some('<') | Some('>') | Some('!') => (4, Assoc::Left),
+        Some('+') | Some('-') => (6, Assoc::Left),
+        Some('*') | Some('/') | Some('%') => (7, Assoc::Left),
+        _ => (5, Assoc::Left)
+    }
+}
+
 /// Converts a string slice into lexemes, ignoring whitespace.
 pub struct Lexer<'a> {
     reader : SymbolReader<'a>,
-    ignore_next_symbol : bool
+    ignore_next_symbol : bool,
+    follows_dot : bool
 }
 
 impl<'a> Lexer<'a> {
@@ -24,12 +44,37 @@ impl<'a> Lexer<'a> {
         self.reader.substring()
     }
 
+    /// Advances past the contents of a quoted literal, up to and including its closing quote.
+    /// Returns `false` if the end of the line is reached before the literal is closed.
+    fn advance_quoted(&mut self, quote : SymbolKind) -> bool {
+        // escape sequences are validated later, but an escaped quote must not close the literal
+        loop {
+            match self.reader.peek() {
+                x if *x == quote => {
+                    self.reader.advance();
+                    return true;
+                },
+                x if x.is_valid_terminator() => return false,
+                SymbolKind::ReverseSolidus => {
+                    self.reader.advance();
+                    if !self.reader.peek().is_valid_terminator() {
+                        self.reader.advance();
+                    }
+                },
+                _ => {
+                    self.reader.advance();
+                }
+            }
+        }
+    }
+
     /// Returns the next token of the source.
     pub fn generate_token(&mut self) -> TokenKind {
         if self.ignore_next_symbol {
             self.reader.advance();
             self.ignore_next_symbol = false;
         }
+        let follows_dot = std::mem::replace(&mut self.follows_dot, false);
         self.reader.reset_span();
         match self.reader.advance() {
             x if x.is_valid_whitespace() => {
@@ -42,10 +87,53 @@ impl<'a> Lexer<'a> {
             SymbolKind::RightBox => TokenKind::RightBox,
             SymbolKind::LeftBrace => TokenKind::LeftBrace,
             SymbolKind::RightBrace => TokenKind::RightBrace,
-            SymbolKind::Colon => TokenKind::Colon,
+            SymbolKind::Colon => if matches!(self.reader.peek(), SymbolKind::Colon) {
+                self.reader.advance();
+                TokenKind::DoubleColon
+            } else {
+                TokenKind::Colon
+            },
+            SymbolKind::SemiColon => TokenKind::SemiColon,
+            SymbolKind::Comma => TokenKind::Comma,
+            SymbolKind::Dot => {
+                self.follows_dot = true;
+                TokenKind::Dot
+            },
+            SymbolKind::Caret => TokenKind::Caret,
             x if x.is_valid_digit() => {
-                self.reader.advance_while(SymbolKind::is_valid_digit);
-                TokenKind::Integral
+                // the digits are validated later, so that `0xFF` and `1_000` are single tokens
+                self.reader.advance_while(SymbolKind::is_valid_graphic);
+                let prefix = self.substring().get(..2);
+                let radix = match prefix {
+                    Some("0x") | Some("0X") => 16,
+                    Some("0o") | Some("0O") => 8,
+                    Some("0b") | Some("0B") => 2,
+                    _ => 10
+                };
+                // a literal directly after a `.` is an element index, so that `t.0.1` is two
+                // accesses rather than `t.(0.1)`
+                if radix != 10 || follows_dot {
+                    return TokenKind::Integral { radix };
+                }
+                // a `.` only continues the literal if it is followed by a digit, so that `1.f()`
+                // is still a method call
+                let mut float = false;
+                if matches!(self.reader.peek(), SymbolKind::Dot) && self.reader.peek_next().is_valid_digit() {
+                    self.reader.advance();
+                    self.reader.advance_while(SymbolKind::is_valid_graphic);
+                    float = true;
+                }
+                if self.substring().ends_with(['e', 'E'])
+                        && matches!(self.reader.peek(), SymbolKind::Plus | SymbolKind::Minus)
+                        && self.reader.peek_next().is_valid_digit() {
+                    self.reader.advance();
+                    self.reader.advance_while(SymbolKind::is_valid_graphic);
+                }
+                if float || self.substring().contains(['e', 'E']) {
+                    TokenKind::Float
+                } else {
+                    TokenKind::Integral { radix }
+                }
             },
             x if x.is_valid_graphic() => {
                 self.reader.advance_while(SymbolKind::is_valid_graphic);
@@ -54,6 +142,26 @@ impl<'a> Lexer<'a> {
                 match self.substring() {
                     "_" => TokenKind::Hole,
                     "let" => TokenKind::Let,
+                    "mut" => TokenKind::Mut,
+                    "var" => TokenKind::Var,
+                    "fn" => TokenKind::Fn,
+                    "def" => TokenKind::Def,
+                    "data" => TokenKind::Data,
+                    "struct" => TokenKind::Struct,
+                    "impl" => TokenKind::Impl,
+                    "if" => TokenKind::If,
+                    "then" => TokenKind::Then,
+                    "else" => TokenKind::Else,
+                    "true" => TokenKind::True,
+                    "false" => TokenKind::False,
+                    "while" => TokenKind::While,
+                    "do" => TokenKind::Do,
+                    "loop" => TokenKind::Loop,
+                    "break" => TokenKind::Break,
+                    "continue" => TokenKind::Continue,
+                    "as" => TokenKind::As,
+                    "infixl" => TokenKind::Infix { assoc : Assoc::Left },
+                    "infixr" => TokenKind::Infix { assoc : Assoc::Right },
                     _ => TokenKind::Identifier
                 }
             },
@@ -63,10 +171,22 @@ impl<'a> Lexer<'a> {
                     self.reader.reset_span();
                     self.reader.advance_while(|x| !matches!(x, SymbolKind::EoL));
                     TokenKind::Comment
+                } else if self.substring() == "=" {
+                    TokenKind::Equals
                 } else {
-                    TokenKind::Operator { precedence : 0 }
+                    let (precedence, assoc) = default_fixity(self.substring());
+                    TokenKind::Operator { precedence, assoc }
                 }
             },
+            SymbolKind::DoubleQuote => {
+                let closed = self.advance_quoted(SymbolKind::DoubleQuote);
+                TokenKind::String { closed }
+            },
+            SymbolKind::SingleQuote => {
+                // primes are consumed with their identifier, so a `'` here always begins a character
+                let closed = self.advance_quoted(SymbolKind::SingleQuote);
+                TokenKind::Char { closed }
+            },
             SymbolKind::Backtick => {
                 self.reader.reset_span(); // this is used so that identifiers Foo and `Foo` are the same
                 self.reader.advance_while(|x| !matches!(x, SymbolKind::Backtick | SymbolKind::EoL));
@@ -83,7 +203,8 @@ impl<'a> Lexer<'a> {
 impl<'a> From<SymbolReader<'a>> for Lexer<'a> {
     fn from(reader : SymbolReader<'a>) -> Self {
         let ignore_next_symbol = false;
-        Self { reader, ignore_next_symbol }
+        let follows_dot = false;
+        Self { reader, ignore_next_symbol, follows_dot }
     }
 }
 
@@ -99,3 +220,64 @@ impl Into<Span> for Lexer<'_> {
         self.span().clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the kind and substring of each token in this source, ignoring whitespace.
+    fn tokens(src : &str) -> Vec<(TokenKind, &str)> {
+        let mut lexer = Lexer::from(src);
+        let mut tokens = Vec::new();
+        loop {
+            match lexer.generate_token() {
+                TokenKind::EoF => return tokens,
+                TokenKind::Whitestuff => (),
+                kind => tokens.push((kind, lexer.substring()))
+            }
+        }
+    }
+
+    #[test]
+    fn a_dot_only_continues_a_literal_before_a_digit() {
+        assert_eq!(tokens("1.5"), vec![(TokenKind::Float, "1.5")]);
+        assert_eq!(tokens("1.f()"), vec![
+                (TokenKind::Integral { radix : 10 }, "1"),
+                (TokenKind::Dot, "."),
+                (TokenKind::Identifier, "f"),
+                (TokenKind::LeftParen, "("),
+                (TokenKind::RightParen, ")")]);
+        assert_eq!(tokens("1."), vec![(TokenKind::Integral { radix : 10 }, "1"), (TokenKind::Dot, ".")]);
+    }
+
+    #[test]
+    fn an_exponent_after_a_dot_is_not_part_of_a_literal() {
+        assert_eq!(tokens("1.e5"), vec![
+                (TokenKind::Integral { radix : 10 }, "1"),
+                (TokenKind::Dot, "."),
+                (TokenKind::Identifier, "e5")]);
+        assert_eq!(tokens("1.0e5"), vec![(TokenKind::Float, "1.0e5")]);
+    }
+
+    #[test]
+    fn exponents_make_a_literal_float() {
+        assert_eq!(tokens("1e-3"), vec![(TokenKind::Float, "1e-3")]);
+        assert_eq!(tokens("2.5E+10"), vec![(TokenKind::Float, "2.5E+10")]);
+        assert_eq!(tokens("1e3"), vec![(TokenKind::Float, "1e3")]);
+        assert_eq!(tokens("1e-x"), vec![
+                (TokenKind::Float, "1e"),
+                (TokenKind::Operator { precedence : 6, assoc : Assoc::Left }, "-"),
+                (TokenKind::Identifier, "x")]);
+        assert_eq!(tokens("0x1e-3")[0], (TokenKind::Integral { radix : 16 }, "0x1e"));
+    }
+
+    #[test]
+    fn literals_after_a_dot_are_element_indices() {
+        assert_eq!(tokens("t.0.1"), vec![
+                (TokenKind::Identifier, "t"),
+                (TokenKind::Dot, "."),
+                (TokenKind::Integral { radix : 10 }, "0"),
+                (TokenKind::Dot, "."),
+                (TokenKind::Integral { radix : 10 }, "1")]);
+    }
+}
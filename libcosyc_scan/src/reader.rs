@@ -33,6 +33,14 @@ impl<'a> SymbolReader<'a> {
         &self.current
     }
 
+    /// Peeks at the `SymbolKind` after the next one.
+    pub fn peek_next(&self) -> SymbolKind {
+        self.chars.clone()
+                .next()
+                .map(|(_, c)| SymbolKind::identify(c))
+                .unwrap_or(SymbolKind::EoF)
+    }
+
     /// Advances the reader and returns the next `SymbolKind`.
     pub fn advance(&mut self) -> SymbolKind {
         let future = if let Some((i, c)) = self.chars.next() {
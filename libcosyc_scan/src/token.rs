@@ -1,3 +1,10 @@
+/// Represents the associativity of an infix operator.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Assoc {
+    Left,
+    Right
+}
+
 /// Represents token types.
 #[derive(PartialEq, Eq, Debug)]
 pub enum TokenKind {
@@ -8,16 +15,53 @@ pub enum TokenKind {
     LeftBrace,
     RightBrace,
     Colon,
+    DoubleColon,
+    SemiColon,
+    Comma,
+    Dot,
+    Caret,
+    Equals,
     Hole,
     Identifier,
     RawIdentifier {
         closed : bool
     },
     Operator {
-        precedence : u8
+        precedence : u8,
+        assoc : Assoc
+    },
+    Integral {
+        radix : u8
+    },
+    Float,
+    String {
+        closed : bool
+    },
+    Char {
+        closed : bool
     },
-    Integral,
     Let,
+    Mut,
+    Var,
+    Fn,
+    Def,
+    Data,
+    Struct,
+    Impl,
+    If,
+    Then,
+    Else,
+    True,
+    False,
+    While,
+    Do,
+    Loop,
+    Break,
+    Continue,
+    As,
+    Infix {
+        assoc : Assoc
+    },
     Comment,
     Whitestuff,
     EoF,
@@ -36,6 +80,13 @@ impl TokenKind {
     /// Returns whether this token indicates a terminal value.
     pub fn is_terminal(&self) -> bool {
         self.is_identifier() || matches!(self,
-                Self::Integral)
+                Self::Integral { .. }
+                | Self::Float
+                | Self::String { .. }
+                | Self::Char { .. }
+                | Self::True
+                | Self::False
+                | Self::Break
+                | Self::Continue)
     }
 }